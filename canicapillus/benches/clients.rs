@@ -0,0 +1,60 @@
+//! Compares fetching with fresh clients on every execution against reusing the clients
+//! of one fetcher, on a local mock server.
+//!
+//! ```sh
+//! cargo bench -p woodpecker-canicapillus --features testing --bench clients
+//! ```
+
+use std::time::{Duration, Instant};
+
+use woodpecker_canicapillus::{
+    api::API,
+    fetcher::Fetcher,
+    prebuilt::FetchFeed,
+    testing::{Fixture, MockServer},
+    HoleSet,
+};
+
+const ROUNDS: usize = 200;
+/// The pages requested by every execution of [`FetchFeed`].
+const PAGES: usize = 4;
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{:<8} {:>8.3} ms/execution {:>10.1} pages/s",
+        name,
+        elapsed.as_secs_f64() * 1000.0 / ROUNDS as f64,
+        (ROUNDS * PAGES) as f64 / elapsed.as_secs_f64(),
+    );
+}
+
+async fn bench(server: &MockServer) {
+    // warm up the server
+    let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+    fetcher.fetch(&FetchFeed).execute().await.unwrap();
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        fetcher.fetch(&FetchFeed).execute().await.unwrap();
+    }
+    report("fresh", start.elapsed());
+
+    let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        fetcher.fetch(&FetchFeed).execute().await.unwrap();
+    }
+    report("reused", start.elapsed());
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let server = MockServer::start(Fixture::default()).await;
+        bench(&server).await;
+    });
+}
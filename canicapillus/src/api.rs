@@ -1,11 +1,13 @@
-use std::env;
-
-use dotenv::dotenv;
+use std::sync::Arc;
 
 use url::Url;
 
 use crate::{
-    common::{Endpoint, Location},
+    common::{resolve_media, Action, ActionEndpoint, Endpoint, Location, MediaEndpoint},
+    credential::{with_user_token, CredentialError, EnvToken, TokenPool, TokenProvider},
+    fetcher::FetcherError,
+    hole::HoleKind,
+    prebuilt::Thread,
     HoleSet, ReplySet,
 };
 
@@ -17,6 +19,7 @@ const DEFAULT_PARAMS: [(&str, &str); 2] =
 pub struct API {
     endpoint: Url,
     user_token: String,
+    pool: Option<Arc<TokenPool>>,
 }
 
 impl Default for API {
@@ -24,23 +27,9 @@ impl Default for API {
     ///
     /// # Panics
     ///
-    /// Panics if `WOODPECKER_USER_TOKEN` is not found in env vars.
+    /// Panics if `WOODPECKER_USER_TOKEN` is not found in env vars, see [`API::try_default`].
     fn default() -> Self {
-        dotenv().ok();
-        let user_token =
-            env::var("WOODPECKER_USER_TOKEN").expect("WOODPECKER_USER_TOKEN not found.");
-        let base = Url::parse_with_params(
-            DEFAULT_API_BASE,
-            DEFAULT_PARAMS
-                .iter()
-                .chain([("user_token", user_token.as_str())].iter()),
-        )
-        .unwrap();
-
-        API {
-            endpoint: base,
-            user_token,
-        }
+        API::try_default().expect("WOODPECKER_USER_TOKEN not found.")
     }
 }
 
@@ -49,36 +38,134 @@ impl API {
     ///
     /// # Panics
     ///
-    /// Panics if can not parse an absolute URL from `endpoint`.
+    /// Panics if can not parse an absolute URL from `endpoint`, see [`API::try_new`].
     pub fn new(base: &str, params: Option<&[(&str, &str)]>, user_token: &str) -> Self {
+        API::try_new(base, params, user_token).unwrap()
+    }
+
+    /// Returns a new API instance, or [`FetcherError::MalformedUrl`] if can not parse an
+    /// absolute URL from `base`.
+    pub fn try_new(
+        base: &str,
+        params: Option<&[(&str, &str)]>,
+        user_token: &str,
+    ) -> Result<Self, FetcherError> {
         let base = Url::parse_with_params(
             base,
             params
                 .into_iter()
                 .flatten()
                 .chain([("user_token", user_token)].iter()),
-        )
-        .unwrap();
+        )?;
 
-        API {
+        Ok(API {
             endpoint: base,
             user_token: String::from(user_token),
-        }
+            pool: None,
+        })
     }
 
+    /// Returns a default API instance, or [`FetcherError::CredentialFail`] if
+    /// `WOODPECKER_USER_TOKEN` is not found in env vars.
+    pub fn try_default() -> Result<Self, FetcherError> {
+        API::from_provider(&EnvToken::default())
+    }
+
+    /// Returns a default API instance with the user token of `provider`.
+    pub fn from_provider(provider: &dyn TokenProvider) -> Result<Self, FetcherError> {
+        let user_token = provider.token()?;
+        API::try_new(DEFAULT_API_BASE, Some(&DEFAULT_PARAMS[..]), &user_token)
+    }
+
+    /// Returns a new API instance taking turns among the tokens of `pool` request by request,
+    /// or [`CredentialError::TokenExpired`] if every token of `pool` is expired.
+    pub fn with_token_pool(
+        base: &str,
+        params: Option<&[(&str, &str)]>,
+        pool: Arc<TokenPool>,
+    ) -> Result<Self, FetcherError> {
+        let user_token = match pool.active().first() {
+            Some(token) => String::from(*token),
+            None => return Err(CredentialError::TokenExpired { msg: None }.into()),
+        };
+        Ok(API {
+            pool: Some(pool),
+            ..API::try_new(base, params, &user_token)?
+        })
+    }
+
+    /// The user token given on construction, or the first active token of the [`TokenPool`].
     pub fn user_token(&self) -> &str {
         &self.user_token
     }
+
+    /// The user token of the next request.
+    ///
+    /// With a [`TokenPool`], every call takes the next token of the pool, failing with
+    /// [`CredentialError::TokenExpired`] once every token of the pool is expired.
+    pub fn next_token(&self) -> Result<&str, CredentialError> {
+        match &self.pool {
+            Some(pool) => pool
+                .next()
+                .ok_or(CredentialError::TokenExpired { msg: None }),
+            None => Ok(&self.user_token),
+        }
+    }
+
+    pub fn token_pool(&self) -> Option<&Arc<TokenPool>> {
+        self.pool.as_ref()
+    }
+
+    /// The endpoint carrying the user token of the next request.
+    fn endpoint(&self) -> Result<Url, CredentialError> {
+        if self.pool.is_none() {
+            return Ok(self.endpoint.clone());
+        }
+        Ok(with_user_token(&self.endpoint, self.next_token()?))
+    }
 }
 
 impl Endpoint<HoleSet> for API {
-    fn locate(&self, location: &dyn Location<HoleSet>) -> Url {
-        location.locate(self.endpoint.clone())
+    fn locate(&self, location: &dyn Location<HoleSet>) -> Result<Url, CredentialError> {
+        Ok(location.locate(self.endpoint()?))
+    }
+
+    fn token_rotation(&self) -> Option<Arc<TokenPool>> {
+        self.pool.clone()
     }
 }
 
 impl Endpoint<ReplySet> for API {
-    fn locate(&self, location: &dyn Location<ReplySet>) -> Url {
-        location.locate(self.endpoint.clone())
+    fn locate(&self, location: &dyn Location<ReplySet>) -> Result<Url, CredentialError> {
+        Ok(location.locate(self.endpoint()?))
+    }
+
+    fn token_rotation(&self) -> Option<Arc<TokenPool>> {
+        self.pool.clone()
+    }
+}
+
+impl Endpoint<Thread> for API {
+    fn locate(&self, location: &dyn Location<Thread>) -> Result<Url, CredentialError> {
+        Ok(location.locate(self.endpoint()?))
     }
-}
\ No newline at end of file
+
+    fn token_rotation(&self) -> Option<Arc<TokenPool>> {
+        self.pool.clone()
+    }
+}
+
+impl ActionEndpoint for API {
+    fn locate_action<T>(&self, action: &T) -> Result<Url, CredentialError>
+    where
+        T: Action,
+    {
+        Ok(action.locate(self.endpoint()?))
+    }
+}
+
+impl MediaEndpoint for API {
+    fn locate_media(&self, kind: &HoleKind) -> Option<Url> {
+        resolve_media(&self.endpoint, kind)
+    }
+}
@@ -0,0 +1,117 @@
+use reqwest::{
+    multipart::{Form, Part},
+    RequestBuilder, Response,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
+
+use super::{read_page, ParseResourceError};
+use crate::credential::CredentialError;
+
+/// A write action of the hole backend, e.g. posting a hole.
+///
+/// While a [`Location`](super::Location) is requested by GET, an action is requested
+/// by POST with an [`ActionBody`].
+pub trait Action
+where
+    Self: Sync,
+{
+    /// What the backend returns on success, e.g. the id of a new hole.
+    type Output;
+
+    /// The location of the action.
+    fn locate(&self, url: Url) -> Url;
+
+    fn body(&self) -> ActionBody;
+
+    /// Reads the output from the `data` field of a successful response, [`Value::Null`] if absent.
+    fn output(data: Value) -> Result<Self::Output, serde_json::Error>;
+}
+
+/// An endpoint accepting [`Action`]s.
+pub trait ActionEndpoint {
+    /// The url of `action`, or a [`CredentialError`] if no user token is left to request it.
+    fn locate_action<T>(&self, action: &T) -> Result<Url, CredentialError>
+    where
+        T: Action;
+}
+
+/// The body of an [`Action`] request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionBody {
+    Empty,
+    /// An `application/x-www-form-urlencoded` body.
+    Form(Vec<(String, String)>),
+    /// A `multipart/form-data` body, for uploads.
+    Multipart(Vec<(String, ActionPart)>),
+}
+
+/// A field of a [`ActionBody::Multipart`] body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionPart {
+    Text(String),
+    File {
+        filename: String,
+        /// The MIME type, guessed from `filename` if `None`.
+        mime: Option<String>,
+        bytes: Vec<u8>,
+    },
+}
+
+impl ActionBody {
+    /// Attaches the body to a request.
+    pub(crate) fn attach(self, request: RequestBuilder) -> Result<RequestBuilder, reqwest::Error> {
+        Ok(match self {
+            ActionBody::Empty => request,
+            ActionBody::Form(fields) => request.form(&fields),
+            ActionBody::Multipart(fields) => {
+                let mut form = Form::new();
+                for (name, part) in fields {
+                    let part = match part {
+                        ActionPart::Text(text) => Part::text(text),
+                        ActionPart::File {
+                            filename,
+                            mime,
+                            bytes,
+                        } => {
+                            let mime = mime.unwrap_or_else(|| {
+                                mime_guess::from_path(&filename)
+                                    .first_or_octet_stream()
+                                    .to_string()
+                            });
+                            Part::bytes(bytes).file_name(filename).mime_str(&mime)?
+                        }
+                    };
+                    form = form.part(name, part);
+                }
+                request.multipart(form)
+            }
+        })
+    }
+}
+
+/// The response of an [`Action`].
+#[derive(Deserialize)]
+struct RawActionPage {
+    #[serde(default)]
+    data: Value,
+}
+
+/// Reads the output of `A` from `response`, reporting backend failures like [`read_page`].
+pub async fn read_action<A>(response: Response) -> Result<A::Output, ParseResourceError>
+where
+    A: Action + ?Sized,
+{
+    let status = response.status();
+    let page: RawActionPage = read_page(response).await?;
+    let snippet = page.data.to_string();
+    A::output(page.data).map_err(|source| ParseResourceError::Malformed {
+        status,
+        path: String::from("data"),
+        line: source.line(),
+        column: source.column(),
+        snippet,
+        source,
+    })
+}
@@ -0,0 +1,30 @@
+use url::Url;
+
+use crate::hole::HoleKind;
+
+/// The directory of image media, relative to the API.
+const IMAGE_DIR: &str = "images/";
+/// The directory of audio media, relative to the API.
+const AUDIO_DIR: &str = "audios/";
+
+/// Where the hole backend serves the media of image and audio holes.
+pub trait MediaEndpoint {
+    /// The url of the media of a hole, `None` for a text hole or an empty media url.
+    fn locate_media(&self, kind: &HoleKind) -> Option<Url>;
+}
+
+/// Resolves the media url of a hole against the url of the API.
+///
+/// pkuhelper serves media next to the API, under `images/` and `audios/`. Absolute media
+/// urls are kept as they are.
+pub fn resolve_media(base: &Url, kind: &HoleKind) -> Option<Url> {
+    let (dir, url) = match kind {
+        HoleKind::Text => return None,
+        HoleKind::Image { url } => (IMAGE_DIR, url),
+        HoleKind::Audio { url } => (AUDIO_DIR, url),
+    };
+    if url.is_empty() {
+        return None;
+    }
+    base.join(dir).and_then(|dir| dir.join(url)).ok()
+}
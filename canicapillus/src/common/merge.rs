@@ -0,0 +1,168 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    hole::{history, reply::ReplyID, HoleID},
+    HoleEntry, ReplyEntry,
+};
+
+/// How to resolve two copies of the same entry, e.g. a hole showing up on two pages.
+///
+/// Copies are resolved in page order, so the result does not depend on the order
+/// in which pages arrive.
+pub enum MergeStrategy<E> {
+    /// Keeps the copy of the earliest page.
+    FirstWins,
+    /// Keeps the copy of the latest page.
+    LastWins,
+    /// Keeps the copy with the newest snapshot, or the copy of the latest page on a tie.
+    NewestSnapshot,
+    /// Keeps the newest snapshot with the largest counters seen on any copy,
+    /// e.g. `reply` and `likenum` of a hole.
+    MaxCounters,
+    /// Resolves the copy of the earlier page and the copy of the later page.
+    Custom(Arc<dyn Fn(E, E) -> E>),
+}
+
+impl<E> MergeStrategy<E> {
+    pub fn custom(resolve: impl Fn(E, E) -> E + 'static) -> Self {
+        MergeStrategy::Custom(Arc::new(resolve))
+    }
+}
+
+impl<E> Clone for MergeStrategy<E> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::FirstWins => Self::FirstWins,
+            Self::LastWins => Self::LastWins,
+            Self::NewestSnapshot => Self::NewestSnapshot,
+            Self::MaxCounters => Self::MaxCounters,
+            Self::Custom(resolve) => Self::Custom(Arc::clone(resolve)),
+        }
+    }
+}
+
+impl<E> fmt::Debug for MergeStrategy<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FirstWins => f.write_str("FirstWins"),
+            Self::LastWins => f.write_str("LastWins"),
+            Self::NewestSnapshot => f.write_str("NewestSnapshot"),
+            Self::MaxCounters => f.write_str("MaxCounters"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// An entry of which the backend may return several copies.
+pub trait SnapshotEntry: Ord {
+    /// Identifies copies of the same entry.
+    type Key: Ord;
+
+    fn key(&self) -> Self::Key;
+
+    fn snapshot(&self) -> DateTime<Utc>;
+
+    /// Raises the counters of `self` to those of `other`, if larger.
+    fn raise_counters(&mut self, other: &Self) {
+        let _ = other;
+    }
+
+    /// Merges two sets, keeping the newest snapshot of every entry, that of `rhs` on a tie.
+    fn merge_newest(lhs: BTreeSet<Self>, rhs: BTreeSet<Self>) -> BTreeSet<Self>
+    where
+        Self: Sized,
+    {
+        merge_by_key(lhs, rhs, |former, latter| {
+            MergeStrategy::NewestSnapshot.resolve(former, latter)
+        })
+    }
+}
+
+impl SnapshotEntry for HoleEntry {
+    type Key = HoleID;
+
+    fn key(&self) -> HoleID {
+        self.entry.id
+    }
+
+    fn snapshot(&self) -> DateTime<Utc> {
+        self.snapshot
+    }
+
+    fn raise_counters(&mut self, other: &Self) {
+        self.entry.reply = self.entry.reply.max(other.entry.reply);
+        self.entry.likenum = self.entry.likenum.max(other.entry.likenum);
+    }
+
+    fn merge_newest(lhs: BTreeSet<Self>, rhs: BTreeSet<Self>) -> BTreeSet<Self> {
+        history::merge_newest(lhs, rhs)
+    }
+}
+
+impl SnapshotEntry for ReplyEntry {
+    type Key = ReplyID;
+
+    fn key(&self) -> ReplyID {
+        self.entry.id
+    }
+
+    fn snapshot(&self) -> DateTime<Utc> {
+        self.snapshot
+    }
+}
+
+impl<E> MergeStrategy<E>
+where
+    E: SnapshotEntry,
+{
+    /// Resolves the copy of the earlier page, `former`, and the copy of the later page, `latter`.
+    pub fn resolve(&self, former: E, latter: E) -> E {
+        match self {
+            Self::FirstWins => former,
+            Self::LastWins => latter,
+            Self::NewestSnapshot if former.snapshot() > latter.snapshot() => former,
+            Self::NewestSnapshot => latter,
+            Self::MaxCounters => {
+                let (mut newer, older) = if former.snapshot() > latter.snapshot() {
+                    (former, latter)
+                } else {
+                    (latter, former)
+                };
+                newer.raise_counters(&older);
+                newer
+            }
+            Self::Custom(resolve) => resolve(former, latter),
+        }
+    }
+
+    /// Merges two sets, `lhs` holding the entries of earlier pages, keeping a single copy
+    /// of every entry.
+    pub fn merge(&self, lhs: BTreeSet<E>, rhs: BTreeSet<E>) -> BTreeSet<E> {
+        match self {
+            Self::NewestSnapshot => E::merge_newest(lhs, rhs),
+            _ => merge_by_key(lhs, rhs, |former, latter| self.resolve(former, latter)),
+        }
+    }
+}
+
+/// Merges two sets, resolving copies of the same entry by `resolve` in page order.
+fn merge_by_key<E>(lhs: BTreeSet<E>, rhs: BTreeSet<E>, resolve: impl Fn(E, E) -> E) -> BTreeSet<E>
+where
+    E: SnapshotEntry,
+{
+    let mut kept = BTreeMap::new();
+    for entry in lhs.into_iter().chain(rhs) {
+        let entry = match kept.remove(&entry.key()) {
+            Some(former) => resolve(former, entry),
+            None => entry,
+        };
+        kept.insert(entry.key(), entry);
+    }
+    kept.into_values().collect()
+}
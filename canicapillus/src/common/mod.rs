@@ -1,11 +1,28 @@
+use std::sync::Arc;
+
 use thiserror::Error;
 use url::Url;
 
+use crate::credential::{CredentialError, TokenPool};
+
+mod action;
+mod media;
+mod merge;
 mod resource;
+pub use action::*;
+pub use media::*;
+pub use merge::*;
 pub use resource::*;
 
 pub trait Endpoint<R>: Sized {
-    fn locate(&self, location: &dyn Location<R>) -> Url;
+    /// The url of `location`, or a [`CredentialError`] if no user token is left to request it.
+    fn locate(&self, location: &dyn Location<R>) -> Result<Url, CredentialError>;
+
+    /// The pool the user tokens of urls are taken from, to retry a request rejecting an
+    /// expired token with the next token.
+    fn token_rotation(&self) -> Option<Arc<TokenPool>> {
+        None
+    }
 }
 
 #[derive(Error, Debug)]
@@ -17,8 +34,34 @@ pub enum SwarmError {
     Fireman,
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum Swarm {
-    Concurrent { count: usize, page_size: usize },
-    Sequential { count: usize, page_size: usize },
+    /// Fetches `count` pages at once, on at most `workers` clients.
+    ///
+    /// Without `workers`, as many clients as the [`Fetcher`](crate::fetcher::Fetcher)
+    /// allows are used, 16 by default.
+    Concurrent {
+        count: usize,
+        page_size: usize,
+        workers: Option<usize>,
+    },
+    Sequential {
+        count: usize,
+        page_size: usize,
+    },
+    /// Fetches pages one after another until a page is empty or shorter than `page_size`,
+    /// or until `max_pages` pages are fetched.
+    Exhaustive {
+        page_size: usize,
+        max_pages: Option<usize>,
+    },
+    /// Fetches the first page, then sizes the swarm by the total count it reports,
+    /// fetching the remaining pages concurrently. Without a count, the remaining pages
+    /// are fetched like [`Swarm::Exhaustive`]. At most `max_pages` pages are fetched,
+    /// on at most `workers` clients like [`Swarm::Concurrent`].
+    Auto {
+        page_size: usize,
+        max_pages: Option<usize>,
+        workers: Option<usize>,
+    },
 }
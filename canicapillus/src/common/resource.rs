@@ -1,16 +1,22 @@
 use async_trait::async_trait;
-use reqwest::Response;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use reqwest::{Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
 use thiserror::Error;
 use url::Url;
 
 use crate::{HoleSet, ReplySet, hole::{RawHolePage, reply::RawReplyPage}};
 
-use super::{Swarm, SwarmError};
+use super::{MergeStrategy, Swarm, SwarmError};
 
 pub trait Resource
 where
     Self: Sized + std::fmt::Debug + Default + ParseResource<Self> + MergeResource<Self>,
-{}
+{
+    /// The number of entries in the resource.
+    fn size(&self) -> usize;
+}
 
 /// A resource which can be found on the specific endpoint `E`.
 pub trait Location<R>
@@ -36,13 +42,198 @@ where
     }
 }
 
+/// The number of bytes of a response body kept in a [`ParseResourceError`].
+pub const SNIPPET_LENGTH: usize = 256;
+
+/// The backend `code` rejecting an expired or invalid user token.
+pub const LOGIN_REQUIRED_CODE: i64 = 401;
+
+/// The backend `msg` rejecting an expired or invalid user token, for backends reporting it
+/// under a generic `code`.
+const LOGIN_REQUIRED: &str = "请先登录";
+
 #[derive(Error, Debug)]
-#[error("Parse resource failed.")]
-pub struct ParseResourceError;
+pub enum ParseResourceError {
+    /// Fails to read the response body.
+    #[error("Fails to read the response body.")]
+    Body(#[from] reqwest::Error),
+
+    /// The backend responds with a non-success HTTP status.
+    #[error("Backend responds with HTTP {status}.")]
+    Status { status: StatusCode, snippet: String },
+
+    /// The backend reports a failure through the `code` and `msg` fields.
+    #[error("Backend fails with code {code}: {}.", msg.as_deref().unwrap_or("no message"))]
+    Backend {
+        status: StatusCode,
+        code: i64,
+        msg: Option<String>,
+        snippet: String,
+    },
+
+    /// The response body is not the expected resource.
+    #[error("Malformed resource at `{path}` (line {line}, column {column}).")]
+    Malformed {
+        status: StatusCode,
+        /// The path of the failing field, e.g. `data[3].pid`.
+        path: String,
+        line: usize,
+        column: usize,
+        snippet: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl ParseResourceError {
+    /// The HTTP status of the response, if the response has arrived.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Body(e) => e.status(),
+            Self::Status { status, .. }
+            | Self::Backend { status, .. }
+            | Self::Malformed { status, .. } => Some(*status),
+        }
+    }
+
+    /// The `code` reported by the backend.
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            Self::Backend { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// The `msg` reported by the backend.
+    pub fn msg(&self) -> Option<&str> {
+        match self {
+            Self::Backend { msg, .. } => msg.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the backend rejects the user token, asking to log in again.
+    ///
+    /// Checks the `code` first, then falls back to the wording of the `msg`.
+    pub fn is_token_expired(&self) -> bool {
+        match self {
+            Self::Backend {
+                code: LOGIN_REQUIRED_CODE,
+                ..
+            } => true,
+            Self::Backend { msg: Some(msg), .. } => msg.contains(LOGIN_REQUIRED),
+            _ => false,
+        }
+    }
+
+    /// The leading [`SNIPPET_LENGTH`] bytes of the response body.
+    pub fn snippet(&self) -> Option<&str> {
+        match self {
+            Self::Body(_) => None,
+            Self::Status { snippet, .. }
+            | Self::Backend { snippet, .. }
+            | Self::Malformed { snippet, .. } => Some(snippet),
+        }
+    }
+}
+
+/// The fields shared by every page of the hole backend.
+#[derive(Deserialize)]
+struct RawStatus {
+    #[serde(default)]
+    code: Option<i64>,
+    #[serde(default)]
+    msg: Option<String>,
+}
 
-impl From<reqwest::Error> for ParseResourceError {
-    fn from(_: reqwest::Error) -> Self {
-        Self
+fn snippet(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    match body.char_indices().nth(SNIPPET_LENGTH) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.into_owned(),
+    }
+}
+
+/// Reads a page `P` from `response`.
+///
+/// A backend `code` other than 0 is reported as [`ParseResourceError::Backend`]
+/// even if the HTTP status is a success.
+pub async fn read_page<P>(response: Response) -> Result<P, ParseResourceError>
+where
+    P: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.bytes().await?;
+
+    if let Ok(RawStatus {
+        code: Some(code),
+        msg,
+    }) = serde_json::from_slice(&body)
+    {
+        if code != 0 {
+            return Err(ParseResourceError::Backend {
+                status,
+                code,
+                msg,
+                snippet: snippet(&body),
+            });
+        }
+    }
+
+    if !status.is_success() {
+        return Err(ParseResourceError::Status {
+            status,
+            snippet: snippet(&body),
+        });
+    }
+
+    let deserializer = &mut serde_json::Deserializer::from_slice(&body);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let source = e.into_inner();
+        ParseResourceError::Malformed {
+            status,
+            path,
+            line: source.line(),
+            column: source.column(),
+            snippet: snippet(&body),
+            source,
+        }
+    })
+}
+
+/// The metadata of a page, besides its entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageMeta {
+    /// The `code` reported by the backend, 0 on success.
+    pub code: Option<i64>,
+    /// The total number of entries across all pages, e.g. of a search.
+    pub count: Option<usize>,
+    /// The server time the page reflects.
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Whether the user follows the hole, on a page of replies.
+    pub attention: Option<bool>,
+}
+
+impl From<&RawHolePage> for PageMeta {
+    fn from(page: &RawHolePage) -> Self {
+        PageMeta {
+            code: Some(i64::from(page.code)),
+            count: page.count.and_then(|count| usize::try_from(count).ok()),
+            timestamp: page.timestamp,
+            attention: None,
+        }
+    }
+}
+
+impl From<&RawReplyPage> for PageMeta {
+    fn from(page: &RawReplyPage) -> Self {
+        PageMeta {
+            code: Some(i64::from(page.code)),
+            count: None,
+            timestamp: None,
+            attention: Some(page.attention),
+        }
     }
 }
 
@@ -52,6 +243,24 @@ where
     R: Resource,
 {
     async fn parse(response: Response) -> Result<R, ParseResourceError>;
+
+    /// Parses the resource together with the metadata of its page.
+    async fn parse_with_meta(response: Response) -> Result<(R, PageMeta), ParseResourceError>;
+
+    /// Parses the resource on the page `page` of a swarm, as dispatched by its [`Location`].
+    ///
+    /// Pages are parsed alike by default. Resources whose pages differ in shape tell them
+    /// apart by `page`.
+    fn parse_page<'a>(
+        response: Response,
+        page: usize,
+    ) -> BoxFuture<'a, Result<(R, PageMeta), ParseResourceError>>
+    where
+        Self: 'a,
+    {
+        let _ = page;
+        Self::parse_with_meta(response)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -62,38 +271,66 @@ pub trait MergeResource<R>
 where
     R: Resource,
 {
+    /// The entries resolved by a [`MergeStrategy`].
+    type Entry;
+
     fn merge(lhs: R, rhs: R) -> Result<R, MergeResourceError>;
+
+    /// Merges `lhs`, holding earlier pages, with `rhs` according to `strategy`.
+    ///
+    /// Resources without conflicting entries ignore `strategy` by default.
+    fn merge_with(
+        lhs: R,
+        rhs: R,
+        strategy: &MergeStrategy<Self::Entry>,
+    ) -> Result<R, MergeResourceError> {
+        let _ = strategy;
+        Self::merge(lhs, rhs)
+    }
 }
 
 #[macro_export]
 macro_rules! derive_resource_set {
     ($the_field:ident, $the_page:tt, $the_set:tt) => {
-        impl Resource for $the_set {}
+        impl Resource for $the_set {
+            fn size(&self) -> usize {
+                self.len()
+            }
+        }
         
         #[async_trait]
         impl ParseResource<$the_set> for $the_set {
-            async fn parse(
+            async fn parse(response: reqwest::Response) -> Result<$the_set, ParseResourceError> {
+                Ok(Self::parse_with_meta(response).await?.0)
+            }
+
+            async fn parse_with_meta(
                 response: reqwest::Response,
-            ) -> Result<$the_set, ParseResourceError> {
-                let page: Result<$the_page, _> = response.json().await;
-                match page {
-                    Ok(p) => Ok(p.into_iter().collect()),
-                    Err(e) => {
-                        dbg!(&e);
-                        Err(ParseResourceError::from(e))
-                    }
-                }
+            ) -> Result<($the_set, $crate::common::PageMeta), ParseResourceError> {
+                let page: $the_page = $crate::common::read_page(response).await?;
+                let meta = $crate::common::PageMeta::from(&page);
+                Ok((page.into_iter().collect(), meta))
             }
         }
         
         impl MergeResource<$the_set> for $the_set {
+            type Entry = <$the_page as IntoIterator>::Item;
+
             fn merge(
                 mut lhs: $the_set,
                 rhs: $the_set,
-            ) -> Result<$the_set, crate::common::MergeResourceError> {
+            ) -> Result<$the_set, $crate::common::MergeResourceError> {
                 lhs.extend(rhs);
                 Ok(lhs)
             }
+
+            fn merge_with(
+                lhs: $the_set,
+                rhs: $the_set,
+                strategy: &$crate::common::MergeStrategy<Self::Entry>,
+            ) -> Result<$the_set, $crate::common::MergeResourceError> {
+                Ok(strategy.merge(lhs, rhs))
+            }
         }
     };
 }
@@ -0,0 +1,248 @@
+//! User tokens: where to find them, whether they still work, and how to share requests
+//! among several of them.
+
+use std::{
+    collections::HashSet,
+    env, fs, io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use dotenv::dotenv;
+use thiserror::Error;
+
+use crate::fetcher::FetcherError;
+
+/// The env var holding the user token of [`EnvToken::default`].
+pub const USER_TOKEN_VAR: &str = "WOODPECKER_USER_TOKEN";
+
+#[derive(Error, Debug)]
+pub enum CredentialError {
+    /// No provider has a user token.
+    #[error("User token is not found in {0}.")]
+    NotFound(String),
+
+    #[error("Fails to read user token from `{path}`.")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// The backend rejects the user token.
+    #[error("User token is expired: {}.", msg.as_deref().unwrap_or("no message"))]
+    TokenExpired { msg: Option<String> },
+
+    /// The validation probe fails for another reason.
+    #[error("Fails to validate user token.")]
+    Probe(#[source] Box<FetcherError>),
+}
+
+/// A source of a user token.
+///
+/// Closures returning a token are providers too, e.g. to read a token from a system keyring.
+pub trait TokenProvider {
+    fn token(&self) -> Result<String, CredentialError>;
+}
+
+impl<F> TokenProvider for F
+where
+    F: Fn() -> Result<String, CredentialError>,
+{
+    fn token(&self) -> Result<String, CredentialError> {
+        self()
+    }
+}
+
+/// Reads a user token from an env var, or from the `.env` file.
+#[derive(Debug, Clone)]
+pub struct EnvToken {
+    pub var: String,
+}
+
+impl Default for EnvToken {
+    /// Reads `WOODPECKER_USER_TOKEN`.
+    fn default() -> Self {
+        EnvToken {
+            var: String::from(USER_TOKEN_VAR),
+        }
+    }
+}
+
+impl TokenProvider for EnvToken {
+    fn token(&self) -> Result<String, CredentialError> {
+        dotenv().ok();
+        env::var(&self.var)
+            .ok()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| CredentialError::NotFound(format!("env var `{}`", self.var)))
+    }
+}
+
+/// Reads a user token from a file, ignoring surrounding whitespace.
+#[derive(Debug, Clone)]
+pub struct FileToken {
+    pub path: PathBuf,
+}
+
+impl TokenProvider for FileToken {
+    fn token(&self) -> Result<String, CredentialError> {
+        let content = fs::read_to_string(&self.path).map_err(|source| CredentialError::Io {
+            path: self.path.clone(),
+            source,
+        })?;
+        match content.trim() {
+            "" => Err(CredentialError::NotFound(format!(
+                "file `{}`",
+                self.path.display()
+            ))),
+            token => Ok(String::from(token)),
+        }
+    }
+}
+
+/// Tries providers in order, returning the first token found.
+///
+/// ```ignore
+/// let providers = ProviderChain::default()
+///     .or(EnvToken::default())
+///     .or(FileToken { path: PathBuf::from("token.txt") });
+/// let api = API::from_provider(&providers)?;
+/// ```
+#[derive(Default)]
+pub struct ProviderChain {
+    providers: Vec<Box<dyn TokenProvider>>,
+}
+
+impl ProviderChain {
+    pub fn or(mut self, provider: impl TokenProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+}
+
+impl TokenProvider for ProviderChain {
+    /// Fails with the error of the last provider, or [`CredentialError::NotFound`] without any.
+    fn token(&self) -> Result<String, CredentialError> {
+        let mut error = CredentialError::NotFound(String::from("any provider"));
+        for provider in &self.providers {
+            match provider.token() {
+                Ok(token) => return Ok(token),
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+}
+
+/// User tokens of several accounts, taking turns request by request.
+///
+/// Tokens marked expired are skipped. A pool is shared by [`crate::api::API`]s holding it, and a
+/// fetcher marks a token expired once the backend rejects it, repeating the request with the next.
+///
+/// ```ignore
+/// let pool = Arc::new(TokenPool::new(["token-a", "token-b"]));
+/// let mut fetcher = Fetcher::new(API::with_token_pool(DEFAULT_API_BASE, None, Arc::clone(&pool))?);
+/// fetcher.fetch(&FetchFeed).execute().await?;
+/// println!("{} tokens left", pool.active().len());
+/// ```
+#[derive(Debug)]
+pub struct TokenPool {
+    tokens: Vec<String>,
+    next: AtomicUsize,
+    expired: Mutex<HashSet<usize>>,
+}
+
+impl TokenPool {
+    /// # Panics
+    ///
+    /// Panics if `tokens` is empty, see [`TokenPool::try_new`].
+    pub fn new<T>(tokens: impl IntoIterator<Item = T>) -> Self
+    where
+        T: Into<String>,
+    {
+        TokenPool::try_new(tokens).expect("A token pool requires a token.")
+    }
+
+    /// Returns a pool of `tokens`, or [`CredentialError::NotFound`] if `tokens` is empty.
+    pub fn try_new<T>(tokens: impl IntoIterator<Item = T>) -> Result<Self, CredentialError>
+    where
+        T: Into<String>,
+    {
+        let tokens = tokens.into_iter().map(Into::into).collect::<Vec<_>>();
+        if tokens.is_empty() {
+            return Err(CredentialError::NotFound(String::from(
+                "an empty token pool",
+            )));
+        }
+        Ok(TokenPool {
+            tokens,
+            next: AtomicUsize::new(0),
+            expired: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// The next token which is not expired, or `None` if every token is expired.
+    pub fn next(&self) -> Option<&str> {
+        let expired = self.expired.lock().unwrap();
+        (0..self.tokens.len())
+            .map(|_| self.next.fetch_add(1, Ordering::Relaxed) % self.tokens.len())
+            .find(|i| !expired.contains(i))
+            .map(|i| self.tokens[i].as_str())
+    }
+
+    /// Skips `token` from now on.
+    pub fn expire(&self, token: &str) {
+        let mut expired = self.expired.lock().unwrap();
+        expired.extend(
+            self.tokens
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| *t == token)
+                .map(|(i, _)| i),
+        );
+    }
+
+    /// Skips the token requesting `url` from now on.
+    pub fn expire_url(&self, url: &url::Url) {
+        if let Some((_, token)) = url.query_pairs().find(|(key, _)| key == "user_token") {
+            self.expire(&token);
+        }
+    }
+
+    /// Skips the token requesting `url` from now on, returning `url` with the next token
+    /// instead, or `None` if every token is expired.
+    pub fn rotate_url(&self, url: &url::Url) -> Option<url::Url> {
+        self.expire_url(url);
+        self.next().map(|token| with_user_token(url, token))
+    }
+
+    /// Tokens which are not expired.
+    pub fn active(&self) -> Vec<&str> {
+        let expired = self.expired.lock().unwrap();
+        self.tokens
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !expired.contains(i))
+            .map(|(_, token)| token.as_str())
+            .collect()
+    }
+}
+
+/// Returns `url` requested with `token` in place of its user token.
+pub(crate) fn with_user_token(url: &url::Url, token: &str) -> url::Url {
+    let pairs = url
+        .query_pairs()
+        .filter(|(key, _)| key != "user_token")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    let mut url = url.clone();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair("user_token", token);
+    url
+}
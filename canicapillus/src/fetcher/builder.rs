@@ -0,0 +1,233 @@
+use std::{
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Client, Proxy,
+};
+
+use super::{
+    pool::ClientPool, ConcurrencyLimit, Fetcher, FetcherClientBuilder, FetcherError, RateLimiter,
+    RetryPolicy, DEFAULT_WORKERS,
+};
+use crate::common::Endpoint;
+
+const DEFAULT_REFERER: &str = "https://pkuhelper.pku.edu.cn/hole/";
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/80.0.3987.149 Safari/537.36";
+/// How long an idle connection is kept for later requests.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// The interval of TCP keep-alive probes.
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// The options of the clients of a [`Fetcher`], itself a [`FetcherClientBuilder`].
+///
+/// Clients keep connections alive, and speak HTTP/2 if the server offers it. By default,
+/// they send the Referer of pkuhelper and a desktop user agent, and never time out.
+#[derive(Debug)]
+pub struct ClientOptions {
+    headers: HeaderMap,
+    user_agents: Vec<HeaderValue>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxies: Vec<Proxy>,
+    /// The user agent of the next client.
+    next: AtomicUsize,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::REFERER, HeaderValue::from_static(DEFAULT_REFERER));
+        ClientOptions {
+            headers,
+            user_agents: vec![HeaderValue::from_static(DEFAULT_USER_AGENT)],
+            timeout: None,
+            connect_timeout: None,
+            proxies: Vec::new(),
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Sends `value` as the header `name` on every request, replacing the default if any.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Takes turns among `user_agents` client by client, so the clients of a concurrent
+    /// swarm look like different browsers. Without any, no user agent is sent.
+    pub fn user_agents(mut self, user_agents: impl IntoIterator<Item = HeaderValue>) -> Self {
+        self.user_agents = user_agents.into_iter().collect();
+        self
+    }
+
+    /// Fails a request not completed within `timeout`, including reading the body.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fails a request not connected within `timeout`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Requests through `proxy`. Proxies are tried in the order added.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+}
+
+impl FetcherClientBuilder for ClientOptions {
+    fn build(&self) -> Result<Client, FetcherError> {
+        let mut headers = self.headers.clone();
+        if !self.user_agents.is_empty() {
+            let next = self.next.fetch_add(1, Ordering::Relaxed) % self.user_agents.len();
+            headers.insert(header::USER_AGENT, self.user_agents[next].clone());
+        }
+
+        let mut builder = Client::builder()
+            .default_headers(headers)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(TCP_KEEPALIVE);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        for proxy in &self.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Configures a [`Fetcher`] before building it.
+///
+/// ```ignore
+/// let mut fetcher: Fetcher<API, HoleSet> = Fetcher::builder(API::try_default()?)
+///     .timeout(Duration::from_secs(10))
+///     .proxy(Proxy::all("socks5://127.0.0.1:1080")?)
+///     .user_agents([HeaderValue::from_static("woodpecker/0.1")])
+///     .max_workers(4)
+///     .build()?;
+/// ```
+pub struct FetcherBuilder<A, R>
+where
+    A: Endpoint<R>,
+{
+    api: A,
+    options: ClientOptions,
+    client_builder: Option<Box<dyn FetcherClientBuilder>>,
+    retry: RetryPolicy,
+    limiter: Option<RateLimiter>,
+    concurrency: Option<ConcurrencyLimit>,
+    max_workers: usize,
+    phantom: PhantomData<R>,
+}
+
+impl<A, R> FetcherBuilder<A, R>
+where
+    A: Endpoint<R>,
+{
+    /// Starts configuring a [`Fetcher`] requesting `api`.
+    pub fn new(api: A) -> Self {
+        FetcherBuilder {
+            api,
+            options: ClientOptions::default(),
+            client_builder: None,
+            retry: RetryPolicy::default(),
+            limiter: None,
+            concurrency: None,
+            max_workers: DEFAULT_WORKERS,
+            phantom: PhantomData,
+        }
+    }
+
+    /// See [`ClientOptions::header`].
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.options = self.options.header(name, value);
+        self
+    }
+
+    /// See [`ClientOptions::user_agents`].
+    pub fn user_agents(mut self, user_agents: impl IntoIterator<Item = HeaderValue>) -> Self {
+        self.options = self.options.user_agents(user_agents);
+        self
+    }
+
+    /// See [`ClientOptions::timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options = self.options.timeout(timeout);
+        self
+    }
+
+    /// See [`ClientOptions::connect_timeout`].
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.options = self.options.connect_timeout(timeout);
+        self
+    }
+
+    /// See [`ClientOptions::proxy`].
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.options = self.options.proxy(proxy);
+        self
+    }
+
+    /// Builds clients by `client_builder`, ignoring every [`ClientOptions`] set.
+    pub fn client_builder(mut self, client_builder: impl FetcherClientBuilder + 'static) -> Self {
+        self.client_builder = Some(Box::new(client_builder));
+        self
+    }
+
+    /// See [`Fetcher::with_retry`].
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// See [`Fetcher::with_rate_limiter`].
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// See [`Fetcher::with_concurrency_limit`].
+    pub fn concurrency_limit(mut self, limit: ConcurrencyLimit) -> Self {
+        self.concurrency = Some(limit);
+        self
+    }
+
+    /// Requests at most `max_workers` pages of an execution at once, and keeps at most as
+    /// many clients. Workers of a [`Swarm`](crate::common::Swarm) above it are capped.
+    pub fn max_workers(mut self, max_workers: usize) -> Self {
+        self.max_workers = max_workers.max(1);
+        self
+    }
+
+    /// Builds the [`Fetcher`], building a client up front to fail early on bad options.
+    pub fn build(self) -> Result<Fetcher<A, R>, FetcherError> {
+        let client_builder = self
+            .client_builder
+            .unwrap_or_else(|| Box::new(self.options));
+        let clients = ClientPool::new(client_builder);
+        clients.client()?;
+        Ok(Fetcher {
+            clients,
+            api: self.api,
+            retry: self.retry,
+            limiter: self.limiter,
+            concurrency: self.concurrency,
+            max_workers: self.max_workers,
+            phantom: PhantomData,
+        })
+    }
+}
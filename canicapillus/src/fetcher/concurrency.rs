@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+
+use reqwest::StatusCode;
+use tokio::sync::Notify;
+
+use super::FetcherError;
+use crate::common::ParseResourceError;
+
+/// A cap on the requests in flight, shared by every fetcher and execution it is attached to.
+///
+/// Cloning a limit shares the cap, unlike the workers of a [`Swarm`](crate::common::Swarm)
+/// which only cap one execution. An adaptive limit halves its cap whenever the backend
+/// throttles a request by 429 or fails it by 5xx, and raises it by one after a cap's worth
+/// of other requests, within `min..=max`.
+///
+/// ```ignore
+/// let limit = ConcurrencyLimit::adaptive(2, 16);
+/// let mut holes: Fetcher<API, HoleSet> = Fetcher::default().with_concurrency_limit(limit.clone());
+/// let mut replies: Fetcher<API, ReplySet> = Fetcher::default().with_concurrency_limit(limit);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: Mutex<State>,
+    released: Notify,
+}
+
+#[derive(Debug)]
+struct State {
+    limit: usize,
+    min: usize,
+    max: usize,
+    in_flight: usize,
+    /// Requests completed without throttling since the cap last changed.
+    streak: usize,
+}
+
+/// A request in flight, released on drop.
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    inner: Arc<Inner>,
+}
+
+impl ConcurrencyLimit {
+    /// Admits at most `limit` requests at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0.
+    pub fn fixed(limit: usize) -> Self {
+        Self::adaptive(limit, limit)
+    }
+
+    /// Admits at most `max` requests at once, fewer while the backend throttles, but
+    /// never fewer than `min`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is 0 or greater than `max`.
+    pub fn adaptive(min: usize, max: usize) -> Self {
+        assert!(
+            0 < min && min <= max,
+            "A concurrency limit requires 0 < min <= max."
+        );
+        ConcurrencyLimit {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    limit: max,
+                    min,
+                    max,
+                    in_flight: 0,
+                    streak: 0,
+                }),
+                released: Notify::new(),
+            }),
+        }
+    }
+
+    /// The current cap.
+    pub fn limit(&self) -> usize {
+        self.inner.state.lock().unwrap().limit
+    }
+
+    /// The number of requests in flight.
+    pub fn in_flight(&self) -> usize {
+        self.inner.state.lock().unwrap().in_flight
+    }
+
+    /// Waits until a request may start.
+    pub async fn acquire(&self) -> ConcurrencyPermit {
+        loop {
+            // register before checking, so a release in between is not missed
+            let released = self.inner.released.notified();
+            {
+                let mut state = self.inner.state.lock().unwrap();
+                if state.in_flight < state.limit {
+                    state.in_flight += 1;
+                    return ConcurrencyPermit {
+                        inner: Arc::clone(&self.inner),
+                    };
+                }
+            }
+            released.await;
+        }
+    }
+
+    /// Adapts the cap to the outcome of a request.
+    pub fn record<T>(&self, result: &Result<T, FetcherError>) {
+        let mut state = self.inner.state.lock().unwrap();
+        if result.as_ref().err().is_some_and(is_throttled) {
+            state.limit = (state.limit / 2).max(state.min);
+            state.streak = 0;
+        } else {
+            state.streak += 1;
+            if state.streak >= state.limit && state.limit < state.max {
+                state.limit += 1;
+                state.streak = 0;
+                drop(state);
+                self.inner.released.notify_waiters();
+            }
+        }
+    }
+}
+
+/// Whether the backend throttles the request or is overloaded.
+fn is_throttled(error: &FetcherError) -> bool {
+    match error {
+        FetcherError::ParseResourceFail(ParseResourceError::Status { status, .. }) => {
+            *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        }
+        _ => false,
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.inner.state.lock().unwrap().in_flight -= 1;
+        self.inner.released.notify_waiters();
+    }
+}
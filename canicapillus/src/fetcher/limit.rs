@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::{self, Instant};
+use url::Url;
+
+/// The pace of a token bucket: `burst` requests at once, then one request every `period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    /// Admits `count` requests per second, `count` of them at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is 0.
+    pub fn per_second(count: u32) -> Self {
+        assert!(count > 0, "A quota requires a positive count.");
+        Quota {
+            burst: count,
+            period: Duration::from_secs(1) / count,
+        }
+    }
+
+    /// Admits `count` requests per minute, `count` of them at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is 0.
+    pub fn per_minute(count: u32) -> Self {
+        assert!(count > 0, "A quota requires a positive count.");
+        Quota {
+            burst: count,
+            period: Duration::from_secs(60) / count,
+        }
+    }
+
+    /// Sets how many requests are admitted at once, at least 1.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    quota: Quota,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(quota: Quota) -> Self {
+        Bucket {
+            quota,
+            tokens: f64::from(quota.burst.max(1)),
+            updated: Instant::now(),
+        }
+    }
+
+    /// Takes a token, or returns how long to wait for the next one.
+    fn take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let period = self.quota.period.as_secs_f64();
+        let refill = if period > 0.0 {
+            now.duration_since(self.updated).as_secs_f64() / period
+        } else {
+            f64::INFINITY
+        };
+        self.tokens = (self.tokens + refill).min(f64::from(self.quota.burst.max(1)));
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(self.quota.period.mul_f64(1.0 - self.tokens))
+        }
+    }
+}
+
+/// A client-side rate limiter.
+///
+/// Clones of a limiter share their buckets, so a limiter attached to several
+/// [`super::Fetcher`]s paces all of them together. Every request, including
+/// every retry, waits for a token.
+///
+/// Requests are paced by the quota of their `action`, e.g. `search` or `getcomment`,
+/// falling back to the default quota.
+///
+/// ```ignore
+/// let limiter = RateLimiter::new(Quota::per_second(4))
+///     .action("search", Quota::per_minute(30));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    default: Option<Arc<Mutex<Bucket>>>,
+    actions: HashMap<String, Arc<Mutex<Bucket>>>,
+}
+
+impl RateLimiter {
+    /// Returns a limiter pacing every request by `quota`.
+    pub fn new(quota: Quota) -> Self {
+        RateLimiter {
+            default: Some(Arc::new(Mutex::new(Bucket::new(quota)))),
+            actions: HashMap::new(),
+        }
+    }
+
+    /// Returns a limiter which only paces actions configured by [`RateLimiter::action`].
+    pub fn unlimited() -> Self {
+        RateLimiter::default()
+    }
+
+    /// Paces requests of `action` by their own `quota` instead of the default one.
+    ///
+    /// Configure a limiter before cloning it, clones made before do not see this quota.
+    pub fn action(mut self, action: &str, quota: Quota) -> Self {
+        self.actions.insert(
+            String::from(action),
+            Arc::new(Mutex::new(Bucket::new(quota))),
+        );
+        self
+    }
+
+    /// Waits until a request to `url` is admitted.
+    pub async fn acquire(&self, url: &Url) {
+        let action = url
+            .query_pairs()
+            .find(|(key, _)| key == "action")
+            .map(|(_, value)| value);
+        let bucket = action
+            .and_then(|action| self.actions.get(action.as_ref()))
+            .or(self.default.as_ref());
+        let bucket = match bucket {
+            Some(bucket) => bucket,
+            None => return,
+        };
+
+        loop {
+            let taken = bucket.lock().unwrap().take();
+            match taken {
+                Ok(()) => return,
+                Err(wait) => time::sleep(wait).await,
+            }
+        }
+    }
+}
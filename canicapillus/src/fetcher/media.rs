@@ -0,0 +1,373 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use futures::{stream, StreamExt};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::fs;
+use url::Url;
+
+use super::{send_with_policy, Fetcher, FetcherError};
+use crate::{
+    common::{Endpoint, MediaEndpoint, ParseResourceError},
+    HoleEntry,
+};
+
+/// The default size limit of a media file, 16 MiB.
+pub const DEFAULT_MAX_MEDIA_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Media types accepted by default, as prefixes.
+const DEFAULT_MEDIA_TYPES: [&str; 2] = ["image/", "audio/"];
+
+/// Extensions of the files of every sniffed media type.
+const EXTENSIONS: [(&str, &str); 13] = [
+    ("image/jpeg", "jpeg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/bmp", "bmp"),
+    ("audio/mpeg", "mp3"),
+    ("audio/aac", "aac"),
+    ("audio/mp4", "m4a"),
+    ("audio/ogg", "ogg"),
+    ("audio/wav", "wav"),
+    ("audio/flac", "flac"),
+    ("audio/amr", "amr"),
+    ("video/mp4", "mp4"),
+];
+
+#[derive(Error, Debug)]
+pub enum MediaError {
+    /// The media is larger than the limit of the cache.
+    #[error("Media at {url} exceeds {limit} bytes.")]
+    TooLarge { url: Url, limit: u64 },
+
+    /// The media is of an unknown type, or of a type the cache does not accept.
+    #[error("Media at {url} is of an unsupported type {}.", mime.as_deref().unwrap_or("unknown"))]
+    UnsupportedType { url: Url, mime: Option<String> },
+
+    #[error("Fails to cache media at `{path}`.")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// A media file in a [`MediaCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Media {
+    /// Where the media is downloaded from.
+    pub url: Url,
+    pub path: PathBuf,
+    /// The SHA-256 digest of the content, in lowercase hex.
+    pub sha256: String,
+    /// The media type sniffed from the content.
+    pub mime: String,
+    pub size: u64,
+}
+
+/// A hole with its media, see [`Fetcher::attach_media`].
+#[derive(Debug)]
+pub struct MediaEntry {
+    pub entry: HoleEntry,
+    /// The media of the hole, `None` for a text hole.
+    pub media: Result<Option<Media>, FetcherError>,
+}
+
+/// A directory of media files named by the SHA-256 digest of their content.
+///
+/// Files are stored as `objects/<2 hex digits>/<digest>.<extension>`, so the same media
+/// under different urls is stored once. `urls/<digest of url>` remembers the content of
+/// every url downloaded, so a url is downloaded once.
+///
+/// ```ignore
+/// let cache = MediaCache::new("media").max_size(4 * 1024 * 1024);
+/// let holes = fetcher.fetch(&FetchFeed).execute().await?;
+/// for media in fetcher.attach_media(holes, &cache).await {
+///     dbg!(media.entry.entry.id, media.media.map(|m| m.map(|m| m.path)));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MediaCache {
+    root: PathBuf,
+    max_size: u64,
+    types: Vec<String>,
+}
+
+impl MediaCache {
+    /// Caches images and audios of at most [`DEFAULT_MAX_MEDIA_SIZE`] under `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        MediaCache {
+            root: root.into(),
+            max_size: DEFAULT_MAX_MEDIA_SIZE,
+            types: DEFAULT_MEDIA_TYPES.map(String::from).to_vec(),
+        }
+    }
+
+    /// Rejects media larger than `max_size` bytes, without downloading the rest.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Accepts only media of `types`, either full types like `image/png` or prefixes
+    /// ending with `/` like `audio/`.
+    pub fn types(mut self, types: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.types = types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of the content of digest `sha256` and type `mime`, `None` unless `sha256`
+    /// is a SHA-256 digest in lowercase hex.
+    pub fn path_of(&self, sha256: &str, mime: &str) -> Option<PathBuf> {
+        is_digest(sha256).then(|| self.object_path(sha256, mime))
+    }
+
+    fn object_path(&self, sha256: &str, mime: &str) -> PathBuf {
+        let extension = EXTENSIONS
+            .iter()
+            .find(|(m, _)| *m == mime)
+            .map_or("bin", |(_, extension)| extension);
+        self.root
+            .join("objects")
+            .join(&sha256[..2])
+            .join(format!("{}.{}", sha256, extension))
+    }
+
+    fn index_path(&self, url: &Url) -> PathBuf {
+        self.root.join("urls").join(hex_digest(url.as_str()))
+    }
+
+    fn accepts(&self, mime: &str) -> bool {
+        self.types
+            .iter()
+            .any(|t| mime == t || (t.ends_with('/') && mime.starts_with(t.as_str())))
+    }
+
+    /// The media downloaded from `url` before, if still in the cache.
+    pub async fn lookup(&self, url: &Url) -> Option<Media> {
+        let index = fs::read_to_string(self.index_path(url)).await.ok()?;
+        let (sha256, mime) = index.trim().split_once(' ')?;
+        let path = self.path_of(sha256, mime)?;
+        let size = fs::metadata(&path).await.ok()?.len();
+        Some(Media {
+            url: url.clone(),
+            path,
+            sha256: String::from(sha256),
+            mime: String::from(mime),
+            size,
+        })
+    }
+
+    /// Stores `content` downloaded from `url`, after checking its size and type.
+    pub async fn store(&self, url: &Url, content: &[u8]) -> Result<Media, MediaError> {
+        if content.len() as u64 > self.max_size {
+            return Err(MediaError::TooLarge {
+                url: url.clone(),
+                limit: self.max_size,
+            });
+        }
+        let mime = sniff(content);
+        if !mime.is_some_and(|mime| self.accepts(mime)) {
+            return Err(MediaError::UnsupportedType {
+                url: url.clone(),
+                mime: mime.map(String::from),
+            });
+        }
+        let mime = mime.unwrap();
+
+        let sha256 = hex_digest(content);
+        let path = self.object_path(&sha256, mime);
+        if fs::metadata(&path).await.is_err() {
+            write_atomically(&path, content).await?;
+        }
+        let index = format!("{} {}\n", sha256, mime);
+        write_atomically(&self.index_path(url), index.as_bytes()).await?;
+        Ok(Media {
+            url: url.clone(),
+            path,
+            sha256,
+            mime: String::from(mime),
+            size: content.len() as u64,
+        })
+    }
+}
+
+/// Tells apart the temporary files of concurrent writers in a process.
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes a file through a temporary file, so a crash never leaves a partial file.
+///
+/// Every writer has a temporary file of its own, and a destination written meanwhile by
+/// another writer counts as written.
+async fn write_atomically(path: &Path, content: &[u8]) -> Result<(), MediaError> {
+    let io = |source| MediaError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await.map_err(io)?;
+    }
+    let write = WRITES.fetch_add(1, Ordering::Relaxed);
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(format!(".{}-{}.partial", process::id(), write));
+    let partial = PathBuf::from(partial);
+    let result = match fs::write(&partial, content).await {
+        Ok(()) => fs::rename(&partial, path).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            fs::remove_file(&partial).await.ok();
+            match fs::metadata(path).await {
+                Ok(_) => Ok(()),
+                Err(_) => Err(io(e)),
+            }
+        }
+    }
+}
+
+/// Whether `digest` is a SHA-256 digest in lowercase hex, as named by a [`MediaCache`].
+fn is_digest(digest: &str) -> bool {
+    digest.len() == 64
+        && digest
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn hex_digest(content: impl AsRef<[u8]>) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Sniffs the media type of `content` from its leading bytes.
+pub fn sniff(content: &[u8]) -> Option<&'static str> {
+    let at = |offset: usize, magic: &[u8]| content.get(offset..offset + magic.len()) == Some(magic);
+    let mime = if at(0, b"\xFF\xD8\xFF") {
+        "image/jpeg"
+    } else if at(0, b"\x89PNG\r\n\x1A\n") {
+        "image/png"
+    } else if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        "image/gif"
+    } else if at(0, b"RIFF") && at(8, b"WEBP") {
+        "image/webp"
+    } else if at(0, b"RIFF") && at(8, b"WAVE") {
+        "audio/wav"
+    } else if at(0, b"BM") {
+        "image/bmp"
+    } else if at(0, b"ID3") || at(0, b"\xFF\xFB") || at(0, b"\xFF\xF3") || at(0, b"\xFF\xF2") {
+        "audio/mpeg"
+    } else if at(0, b"\xFF\xF1") || at(0, b"\xFF\xF9") {
+        "audio/aac"
+    } else if at(0, b"OggS") {
+        "audio/ogg"
+    } else if at(0, b"fLaC") {
+        "audio/flac"
+    } else if at(0, b"#!AMR") {
+        "audio/amr"
+    } else if at(4, b"ftypM4A") {
+        "audio/mp4"
+    } else if at(4, b"ftyp") {
+        "video/mp4"
+    } else {
+        return None;
+    };
+    Some(mime)
+}
+
+/// Downloads `url`, failing as soon as the body exceeds `limit` bytes.
+async fn download(client: &Client, url: &Url, limit: u64) -> Result<Vec<u8>, FetcherError> {
+    let too_large = || MediaError::TooLarge {
+        url: url.clone(),
+        limit,
+    };
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(FetcherError::RequestFail)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ParseResourceError::Status {
+            status,
+            snippet: String::new(),
+        }
+        .into());
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit)
+    {
+        return Err(too_large().into());
+    }
+    let mut content = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(ParseResourceError::Body)? {
+        if (content.len() + chunk.len()) as u64 > limit {
+            return Err(too_large().into());
+        }
+        content.extend_from_slice(&chunk);
+    }
+    Ok(content)
+}
+
+impl<A, R> Fetcher<A, R>
+where
+    A: Endpoint<R> + MediaEndpoint,
+{
+    /// Downloads the media of a hole into `cache`, `None` for a text hole.
+    ///
+    /// Media already downloaded from the same url is not requested again. Requests follow
+    /// the retry policy, the rate limiter and the concurrency limit of the fetcher.
+    pub async fn download_media(
+        &self,
+        entry: &HoleEntry,
+        cache: &MediaCache,
+    ) -> Result<Option<Media>, FetcherError> {
+        let url = match self.api.locate_media(&entry.entry.kind) {
+            Some(url) => url,
+            None => return Ok(None),
+        };
+        if let Some(media) = cache.lookup(&url).await {
+            return Ok(Some(media));
+        }
+
+        let client = self.clients.client()?;
+        let policy = self.send_policy(&self.retry);
+        let (content, _) = send_with_policy(&mut url.clone(), policy, |url| {
+            let client = &client;
+            async move { download(client, &url, cache.max_size).await }
+        })
+        .await;
+        Ok(Some(cache.store(&url, &content?).await?))
+    }
+
+    /// Downloads the media of every hole into `cache`, at most `max_workers` at once,
+    /// keeping the holes in order.
+    pub async fn attach_media(
+        &self,
+        holes: impl IntoIterator<Item = HoleEntry>,
+        cache: &MediaCache,
+    ) -> Vec<MediaEntry> {
+        stream::iter(holes)
+            .map(|entry| async move {
+                let media = self.download_media(&entry, cache).await;
+                MediaEntry { entry, media }
+            })
+            .buffered(self.max_workers)
+            .collect()
+            .await
+    }
+}
@@ -0,0 +1,933 @@
+use std::{future::Future, marker::PhantomData, ops::RangeInclusive, sync::Arc};
+
+use futures::{
+    future,
+    stream::{self, FuturesUnordered, LocalBoxStream, StreamExt},
+};
+use reqwest::{self, Client};
+use thiserror::Error;
+use tokio::{
+    join,
+    sync::{mpsc, Mutex as AsyncMutex},
+    time,
+};
+use url::{ParseError as UrlParseError, Url};
+
+use crate::{
+    api::API,
+    common::{
+        read_action, Action, ActionEndpoint, Endpoint, Location, MergeResource, MergeResourceError,
+        MergeStrategy, PageMeta, ParseResource, ParseResourceError, Resource, Swarm, SwarmError,
+    },
+    credential::{with_user_token, CredentialError, TokenPool},
+    prebuilt::FetchAttention,
+    HoleSet,
+};
+
+mod builder;
+mod concurrency;
+mod limit;
+mod media;
+mod pool;
+mod report;
+mod retry;
+pub use builder::*;
+pub use concurrency::*;
+pub use limit::*;
+pub use media::*;
+use pool::ClientPool;
+pub use report::*;
+pub use retry::*;
+
+#[derive(Error, Debug)]
+pub enum FetcherError {
+    // /// Cannot find user token in env vars.
+    // #[error("WOODPECKER_USER_TOKEN is not found.")]
+    // UserTokenNotFound,
+    /// Failed to build a [`reqwest::Client`].
+    #[error("Fails to build a request client.")]
+    ClientBuildFail(#[from] reqwest::Error),
+
+    /// Fails to send a request or to receive the response, e.g. a connection reset.
+    #[error("Fails to send a request.")]
+    RequestFail(#[source] reqwest::Error),
+
+    #[error("Malformed url.")]
+    MalformedUrl(#[from] UrlParseError),
+
+    #[error("Swarm fails.")]
+    SwarmFail(#[from] SwarmError),
+
+    #[error("Client pool fails.")]
+    ClientPoolFail,
+
+    /// No valid user token is available.
+    #[error("Fails to find a valid user token.")]
+    CredentialFail(#[from] CredentialError),
+
+    #[error("Fails to parse a resource.")]
+    ParseResourceFail(#[from] ParseResourceError),
+
+    #[error("Fails to merge two resources.")]
+    MergeResourceFail(#[from] MergeResourceError),
+
+    /// A media file is rejected or cannot be cached.
+    #[error("Fails to download media.")]
+    MediaFail(#[from] MediaError),
+
+    /// A page of a [`Strictness::FailFast`] swarm fails.
+    #[error("Page {page} of the swarm fails.")]
+    PageFail {
+        page: usize,
+        #[source]
+        source: Box<FetcherError>,
+    },
+
+    /// Too few pages of a [`Strictness::MinSuccessRatio`] swarm succeed.
+    #[error("Only {succeeded} of {total} pages of the swarm succeed.")]
+    SwarmIncomplete {
+        succeeded: usize,
+        total: usize,
+        reports: Vec<PageReport>,
+    },
+}
+
+impl FetcherError {
+    /// Whether the backend rejects the user token, see [`ParseResourceError::is_token_expired`].
+    pub fn is_token_expired(&self) -> bool {
+        match self {
+            Self::ParseResourceFail(e) => e.is_token_expired(),
+            Self::PageFail { source, .. } => source.is_token_expired(),
+            _ => false,
+        }
+    }
+}
+
+pub trait FetcherClientBuilder {
+    /// Builds a client, or fails with [`FetcherError::ClientBuildFail`] typically.
+    fn build(&self) -> Result<Client, FetcherError>;
+}
+
+/// Builds clients with the default [`ClientOptions`].
+pub struct DefaultFetcherClientBuilder;
+
+impl FetcherClientBuilder for DefaultFetcherClientBuilder {
+    fn build(&self) -> Result<Client, FetcherError> {
+        ClientOptions::default().build()
+    }
+}
+
+/// A workload of a [`FetcherClientNode`], a page number and its URL.
+type Workload = Option<(usize, Url)>;
+
+struct FetcherClientNode<R>
+where
+    R: Resource,
+{
+    client: Client,
+    retry: RetryPolicy,
+    limiter: Option<RateLimiter>,
+    concurrency: Option<ConcurrencyLimit>,
+    tokens: Option<Arc<TokenPool>>,
+    rx: Arc<AsyncMutex<mpsc::Receiver<Workload>>>,
+    ret: mpsc::Sender<(usize, Url, Attempted<R>)>,
+}
+
+impl<R> FetcherClientNode<R>
+where
+    R: Resource,
+{
+    async fn work(&mut self) -> Result<(), FetcherError> {
+        loop {
+            let recv = self.rx.lock().await.recv().await;
+            match recv {
+                Some(Some((page, mut url))) => {
+                    let result = self.fetch(page, &mut url).await;
+                    self.ret
+                        .send((page, url, result))
+                        .await
+                        .or(Err(FetcherError::ClientPoolFail))?;
+                }
+                _ => {
+                    self.rx.lock().await.close();
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    async fn fetch(&self, page: usize, url: &mut Url) -> Attempted<R> {
+        let policy = SendPolicy {
+            retry: &self.retry,
+            limiter: self.limiter.as_ref(),
+            concurrency: self.concurrency.as_ref(),
+            tokens: self.tokens.as_deref(),
+        };
+        fetch_page(&self.client, page, url, policy).await
+    }
+}
+
+/// The result of fetching a page with its metadata, and the number of attempts made.
+type Attempted<R> = (Result<(R, PageMeta), FetcherError>, usize);
+
+/// How a request is sent, shared by every request of a [`Fetcher`].
+#[derive(Clone, Copy)]
+struct SendPolicy<'p> {
+    /// The retry policy of failing attempts.
+    retry: &'p RetryPolicy,
+    /// The rate limiter pacing every attempt.
+    limiter: Option<&'p RateLimiter>,
+    /// The cap on attempts in flight.
+    concurrency: Option<&'p ConcurrencyLimit>,
+    /// The pool to take another user token from when the backend rejects an expired one.
+    tokens: Option<&'p TokenPool>,
+}
+
+/// Sends a request to `url` by `send` under `policy`, returning the result and the number of
+/// attempts made.
+///
+/// An attempt rejected for an expired token is repeated with the next token of the pool, without
+/// counting against the retry policy, and `url` is left with the token of the last attempt.
+async fn send_with_policy<T, F, Fut>(
+    url: &mut Url,
+    policy: SendPolicy<'_>,
+    send: F,
+) -> (Result<T, FetcherError>, usize)
+where
+    F: Fn(Url) -> Fut,
+    Fut: Future<Output = Result<T, FetcherError>>,
+{
+    let mut attempts = 0;
+    let mut rotations = 0;
+    loop {
+        attempts += 1;
+        if let Some(limiter) = policy.limiter {
+            limiter.acquire(url).await;
+        }
+        let permit = match policy.concurrency {
+            Some(concurrency) => Some(concurrency.acquire().await),
+            None => None,
+        };
+        let result = send(url.clone()).await;
+        if let Some(concurrency) = policy.concurrency {
+            concurrency.record(&result);
+        }
+        drop(permit);
+        let retry = policy.retry;
+        match result {
+            // every rotation expires a token, so rotating stops once the pool runs out
+            Err(e) if e.is_token_expired() => match policy.tokens.and_then(|p| p.rotate_url(url)) {
+                Some(rotated) => {
+                    *url = rotated;
+                    rotations += 1;
+                }
+                None => return (Err(e), attempts),
+            },
+            Err(e) if attempts - rotations < retry.max_attempts && retry.is_retryable(&e) => {
+                time::sleep(retry.delay(attempts - rotations)).await;
+            }
+            result => return (result, attempts),
+        }
+    }
+}
+
+async fn fetch_page<R>(
+    client: &Client,
+    page: usize,
+    url: &mut Url,
+    policy: SendPolicy<'_>,
+) -> Attempted<R>
+where
+    R: Resource,
+{
+    send_with_policy(url, policy, |url| async move {
+        let res = client
+            .get(url)
+            .send()
+            .await
+            .map_err(FetcherError::RequestFail)?;
+        Ok(R::parse_page(res, page).await?)
+    })
+    .await
+}
+
+/// A [`Fetcher`] is a client requesting the hole backend API.
+pub struct Fetcher<A, R>
+where
+    A: Endpoint<R>,
+{
+    /// The reused clients, built by a [`FetcherClientBuilder`].
+    clients: ClientPool,
+    /// The hole backend API.
+    api: A,
+    /// The default retry policy of executions.
+    retry: RetryPolicy,
+    /// The rate limiter pacing every request.
+    limiter: Option<RateLimiter>,
+    /// The cap on requests in flight, shared with other fetchers.
+    concurrency: Option<ConcurrencyLimit>,
+    /// The maximal number of clients of a concurrent swarm, capping every execution.
+    max_workers: usize,
+    phantom: PhantomData<R>,
+}
+
+pub struct FetcherExecutor<'fch, 'lct, A, R>
+where
+    A: Endpoint<R>,
+    R: Resource,
+{
+    fetcher: &'fch mut Fetcher<A, R>,
+    location: &'lct dyn Location<R>,
+    swarm: Option<Swarm>,
+    strictness: Strictness,
+    retry: RetryPolicy,
+    until: Option<Until<R>>,
+    merge: Option<MergeStrategy<<R as MergeResource<R>>::Entry>>,
+}
+
+/// The default maximal number of clients of a concurrent swarm.
+const DEFAULT_WORKERS: usize = 16;
+
+/// A predicate stopping a swarm, see [`FetcherExecutor::until`].
+type Until<R> = Arc<dyn Fn(&R) -> bool>;
+
+macro_rules! must_be {
+    ($p:pat = $e:expr => $r: expr) => {{
+        if let $p = $e {
+            $r
+        } else {
+            unreachable!()
+        }
+    }};
+    ($e:expr, $p:pat, $r: expr) => {{
+        if let $p = $e {
+            $r
+        } else {
+            unreachable!()
+        }
+    }};
+}
+
+impl<'fch, 'lct, A, R> FetcherExecutor<'fch, 'lct, A, R>
+where
+    A: Endpoint<R>,
+    R: Resource,
+{
+    /// Fetches the resource, merging all pages of the swarm.
+    ///
+    /// Failing pages are treated according to [`FetcherExecutor::strictness`].
+    pub async fn execute(&mut self) -> Result<R, FetcherError> {
+        self.execute_with_report()
+            .await
+            .map(|outcome| outcome.resource)
+    }
+
+    /// Fetches the resource like [`FetcherExecutor::execute`], reporting the outcome of every page.
+    pub async fn execute_with_report(&mut self) -> Result<SwarmOutcome<R>, FetcherError> {
+        let total = match self.swarm {
+            None => Some(1),
+            Some(Swarm::Concurrent { count, .. } | Swarm::Sequential { count, .. }) => Some(count),
+            Some(Swarm::Exhaustive { .. } | Swarm::Auto { .. }) => None,
+        };
+        let mut pages = Self::pages(
+            self.fetcher,
+            self.location,
+            self.swarm.as_ref(),
+            &self.retry,
+            self.until.clone(),
+        );
+
+        let mut fetched = Vec::new();
+        let mut reports = Vec::with_capacity(total.unwrap_or_default());
+        while let Some(Page {
+            page,
+            url,
+            attempts,
+            meta,
+            result,
+        }) = pages.next().await
+        {
+            let result = match result {
+                Ok(new) => {
+                    let size = new.size();
+                    fetched.push((page, new));
+                    Ok(size)
+                }
+                Err(e) if self.swarm.is_none() => return Err(e),
+                Err(e) => Err(e),
+            };
+            let report = PageReport {
+                page,
+                url,
+                attempts,
+                meta,
+                result,
+            };
+            let gives_up = self.strictness.gives_up(&report);
+            reports.push(report);
+            if gives_up {
+                break;
+            }
+        }
+
+        // merges in page order, regardless of the order in which pages arrive
+        fetched.sort_by_key(|(page, _)| *page);
+        let mut resource = R::default();
+        for (_, new) in fetched {
+            resource = match &self.merge {
+                Some(strategy) => R::merge_with(resource, new, strategy)?,
+                None => R::merge(resource, new)?,
+            };
+        }
+
+        let total = total.unwrap_or(reports.len());
+        self.strictness
+            .judge(SwarmOutcome { resource, reports }, total)
+    }
+
+    /// Fetches the pages of the swarm as a stream, yielding every page as soon as it arrives.
+    ///
+    /// Pages of a [`Swarm::Sequential`] arrive in order, while pages of a
+    /// [`Swarm::Concurrent`] arrive in no particular order. Every page is yielded,
+    /// regardless of [`FetcherExecutor::strictness`]; dropping the stream cancels
+    /// the pages in flight.
+    pub fn stream<'x>(self) -> LocalBoxStream<'x, Page<R>>
+    where
+        'fch: 'x,
+        'lct: 'x,
+    {
+        let FetcherExecutor {
+            fetcher,
+            location,
+            swarm,
+            retry,
+            until,
+            ..
+        } = self;
+        Self::pages(fetcher, location, swarm.as_ref(), &retry, until)
+    }
+
+    /// Fetches the entries of every page as a stream, see [`FetcherExecutor::stream`].
+    ///
+    /// A failing page yields its error in place of its entries.
+    pub fn stream_entries<'x>(self) -> LocalBoxStream<'x, Result<R::Item, FetcherError>>
+    where
+        'fch: 'x,
+        'lct: 'x,
+        R: IntoIterator + 'x,
+    {
+        self.stream()
+            .flat_map(|page| match page.result {
+                Ok(resource) => stream::iter(resource).map(Ok).left_stream(),
+                Err(e) => stream::once(future::ready(Err(e))).right_stream(),
+            })
+            .boxed_local()
+    }
+
+    pub fn swarm(mut self, swarm: Option<Swarm>) -> Self {
+        self.swarm = swarm;
+        self
+    }
+
+    /// Stops a sequential or exhaustive swarm after a page satisfying `until`.
+    ///
+    /// The page satisfying `until` is still yielded and merged. A concurrent swarm ignores it,
+    /// while an automatic swarm checks it on the first page, and on later pages only if they
+    /// are fetched exhaustively.
+    ///
+    /// ```ignore
+    /// let since = Utc::now() - Duration::days(1);
+    /// fetcher
+    ///     .fetch(&FetchFeed)
+    ///     .swarm(Some(Swarm::Exhaustive { page_size: 30, max_pages: Some(100) }))
+    ///     .until(move |holes: &HoleSet| holes.iter().any(|h| h.entry.timestamp < since))
+    /// ```
+    pub fn until(mut self, until: impl Fn(&R) -> bool + 'static) -> Self {
+        self.until = Some(Arc::new(until));
+        self
+    }
+
+    /// Sets how copies of the same entry on different pages are resolved.
+    ///
+    /// By default, pages are merged by [`MergeResource::merge`].
+    pub fn merge(mut self, strategy: MergeStrategy<<R as MergeResource<R>>::Entry>) -> Self {
+        self.merge = Some(strategy);
+        self
+    }
+
+    /// Sets the retry policy of every page, overriding the one of the [`Fetcher`].
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets how failing pages of a swarm are treated, [`Strictness::BestEffort`] by default.
+    ///
+    /// A single page fetched without a swarm always fails with its own error.
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    fn pages<'x>(
+        fetcher: &'x Fetcher<A, R>,
+        location: &'x dyn Location<R>,
+        swarm: Option<&Swarm>,
+        retry: &RetryPolicy,
+        until: Option<Until<R>>,
+    ) -> LocalBoxStream<'x, Page<R>> {
+        match swarm {
+            Some(swarm @ Swarm::Concurrent { count, .. }) => {
+                Self::concurrent(fetcher, location, swarm, retry, 1..=*count)
+            }
+            Some(&Swarm::Auto {
+                page_size,
+                max_pages,
+                workers,
+            }) => Self::auto(
+                fetcher, location, page_size, max_pages, retry, until, workers,
+            ),
+            swarm => Self::sequential(fetcher, location, swarm.copied(), retry, until, 1),
+        }
+    }
+
+    /// Fetches the first page, then the remaining pages according to its total count.
+    fn auto<'x>(
+        fetcher: &'x Fetcher<A, R>,
+        location: &'x dyn Location<R>,
+        page_size: usize,
+        max_pages: Option<usize>,
+        retry: &RetryPolicy,
+        until: Option<Until<R>>,
+        workers: Option<usize>,
+    ) -> LocalBoxStream<'x, Page<R>> {
+        let max_pages = max_pages.unwrap_or(usize::MAX);
+        let retry = retry.clone();
+        let head = Self::sequential(
+            fetcher,
+            location,
+            Some(Swarm::Sequential {
+                count: 1,
+                page_size,
+            }),
+            &retry,
+            None,
+            1,
+        );
+
+        stream::once(async move {
+            let (first, _) = head.into_future().await;
+            let tail = match &first {
+                Some(Page {
+                    result: Ok(resource),
+                    meta,
+                    ..
+                }) if max_pages > 1 && !until.as_ref().is_some_and(|until| until(resource)) => {
+                    match meta.count {
+                        Some(count) => {
+                            let count = count.div_ceil(page_size.max(1)).min(max_pages);
+                            let swarm = Swarm::Concurrent {
+                                count,
+                                page_size,
+                                workers,
+                            };
+                            Self::concurrent(fetcher, location, &swarm, &retry, 2..=count)
+                        }
+                        None if resource.size() == 0 || resource.size() < page_size => {
+                            stream::empty().boxed_local()
+                        }
+                        None => {
+                            let swarm = Swarm::Exhaustive {
+                                page_size,
+                                max_pages: Some(max_pages),
+                            };
+                            Self::sequential(fetcher, location, Some(swarm), &retry, until, 2)
+                        }
+                    }
+                }
+                _ => stream::empty().boxed_local(),
+            };
+            stream::iter(first).chain(tail)
+        })
+        .flatten()
+        .boxed_local()
+    }
+
+    /// Fetches pages one after another, or a single page without a swarm.
+    ///
+    /// An exhaustive swarm stops after an empty, short or failing page.
+    fn sequential<'x>(
+        fetcher: &'x Fetcher<A, R>,
+        location: &'x dyn Location<R>,
+        swarm: Option<Swarm>,
+        retry: &RetryPolicy,
+        until: Option<Until<R>>,
+        first: usize,
+    ) -> LocalBoxStream<'x, Page<R>> {
+        const MEANINGLESS: usize = 1;
+        let (count, page_size, exhaustive) = match swarm {
+            None => (MEANINGLESS, MEANINGLESS, false),
+            Some(Swarm::Exhaustive {
+                page_size,
+                max_pages,
+            }) => (max_pages.unwrap_or(usize::MAX), page_size, true),
+            Some(ref swarm) => must_be!(
+                swarm,
+                Swarm::Sequential { count, page_size },
+                (*count, *page_size, false)
+            ),
+        };
+        let client = match fetcher.clients.client() {
+            Ok(client) => client,
+            Err(e) => {
+                return stream::once(future::ready(Page::unrequested(first, None, e))).boxed_local()
+            }
+        };
+        let retry = retry.clone();
+        let tokens = fetcher.api.token_rotation();
+
+        // the next page to fetch, or `None` if the swarm stops
+        stream::unfold(Some(first), move |next| {
+            let client = client.clone();
+            let retry = retry.clone();
+            let tokens = tokens.clone();
+            let until = until.clone();
+            async move {
+                let page = next.filter(|page| *page <= count)?;
+                let url = match fetcher.api.locate(location) {
+                    Ok(url) => url,
+                    Err(e) => return Some((Page::unrequested(page, None, e.into()), None)),
+                };
+                let (page, stops) = match location.dispatch(url, swarm.as_ref(), page, page_size) {
+                    Ok(mut url) => {
+                        let policy = SendPolicy {
+                            tokens: tokens.as_deref(),
+                            ..fetcher.send_policy(&retry)
+                        };
+                        let attempted = fetch_page::<R>(&client, page, &mut url, policy).await;
+                        let page = Page::fetched(page, url, attempted);
+                        let stops = match &page.result {
+                            Ok(resource) => {
+                                (exhaustive
+                                    && (resource.size() == 0 || resource.size() < page_size))
+                                    || until.is_some_and(|until| until(resource))
+                            }
+                            Err(_) => exhaustive,
+                        };
+                        (page, stops)
+                    }
+                    Err(e) => (Page::undispatched(page, e), exhaustive),
+                };
+                let next = (!stops).then(|| page.page + 1);
+                Some((page, next))
+            }
+        })
+        .boxed_local()
+    }
+
+    /// Fetches pages on a pool of [`FetcherClientNode`]s.
+    fn concurrent<'x>(
+        fetcher: &'x Fetcher<A, R>,
+        location: &'x dyn Location<R>,
+        swarm: &Swarm,
+        retry: &RetryPolicy,
+        pages: RangeInclusive<usize>,
+    ) -> LocalBoxStream<'x, Page<R>> {
+        let (page_size, workers) = must_be! {
+            Swarm::Concurrent { page_size, workers, .. } = swarm => (*page_size, *workers)
+        };
+        let workers = workers.map_or(fetcher.max_workers, |workers| {
+            workers.min(fetcher.max_workers)
+        });
+
+        // locate every page before requesting any of them
+        let mut undispatched = Vec::new();
+        let mut workloads = Vec::with_capacity(pages.size_hint().0);
+        for page in pages {
+            let url = match fetcher.api.locate(location) {
+                Ok(url) => url,
+                Err(e) => {
+                    undispatched.push(Page::unrequested(page, None, e.into()));
+                    continue;
+                }
+            };
+            match location.dispatch(url, Some(swarm), page, page_size) {
+                Ok(url) => workloads.push((page, url)),
+                Err(e) => undispatched.push(Page::undispatched(page, e)),
+            }
+        }
+        let undispatched = stream::iter(undispatched);
+        if workloads.is_empty() {
+            return undispatched.boxed_local();
+        }
+
+        let pool_size = workloads.len().min(workers.max(1));
+
+        // init workload channels
+        let (tx, rx) = mpsc::channel(pool_size);
+        let rx = Arc::new(AsyncMutex::new(rx));
+
+        // init result channels, which close as soon as every client quits
+        let (ret, res) = mpsc::channel(pool_size);
+
+        // take clients from the pool, failing every page if any client fails
+        let clients = match fetcher.clients.take(pool_size) {
+            Ok(clients) => clients,
+            Err(e) => {
+                let mut error = Some(e);
+                let failed = workloads
+                    .into_iter()
+                    .map(|(page, url)| {
+                        let e = error.take().unwrap_or(FetcherError::ClientPoolFail);
+                        Page::unrequested(page, Some(url), e)
+                    })
+                    .collect::<Vec<_>>();
+                return undispatched.chain(stream::iter(failed)).boxed_local();
+            }
+        };
+        let tokens = fetcher.api.token_rotation();
+        let clients = clients
+            .into_iter()
+            .map(|client| FetcherClientNode::<R> {
+                client,
+                retry: retry.clone(),
+                limiter: fetcher.limiter.clone(),
+                concurrency: fetcher.concurrency.clone(),
+                tokens: tokens.clone(),
+                rx: Arc::clone(&rx),
+                ret: ret.clone(),
+            })
+            .collect::<Vec<_>>();
+        drop(ret);
+
+        // launch works on clients, return an await handle
+        let work = clients
+            .into_iter()
+            .map(|mut client| async move { client.work().await })
+            .collect::<FuturesUnordered<_>>()
+            .collect::<Vec<_>>();
+
+        // dispatch works for clients, return an await handle
+        let dispatch = async move {
+            for workload in workloads {
+                if tx.send(Some(workload)).await.is_err() {
+                    return;
+                }
+            }
+            for _ in 0..pool_size {
+                if tx.send(None).await.is_err() {
+                    return;
+                }
+            }
+        };
+
+        // drive the clients and the dispatcher along with the results, yielding nothing
+        let background = stream::once(async move {
+            join!(work, dispatch);
+        })
+        .filter_map(|_| future::ready(None));
+
+        let results = stream::unfold(res, |mut res| async move {
+            let (page, url, attempted) = res.recv().await?;
+            Some((Page::fetched(page, url, attempted), res))
+        });
+
+        undispatched
+            .chain(stream::select(results, background))
+            .boxed_local()
+    }
+}
+
+impl<A, R> Fetcher<A, R>
+where
+    A: Endpoint<R>,
+{
+    /// Starts configuring a [`Fetcher`] requesting `api`, see [`FetcherBuilder`].
+    pub fn builder(api: A) -> FetcherBuilder<A, R> {
+        FetcherBuilder::new(api)
+    }
+
+    /// Returns a new [`Fetcher`] requesting `api` with the [`DefaultFetcherClientBuilder`].
+    pub fn new(api: A) -> Self {
+        Fetcher {
+            clients: ClientPool::new(Box::new(DefaultFetcherClientBuilder)),
+            api,
+            retry: RetryPolicy::default(),
+            limiter: None,
+            concurrency: None,
+            max_workers: DEFAULT_WORKERS,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Paces every request of this fetcher by `limiter`.
+    ///
+    /// Attach clones of the same limiter to pace several fetchers together.
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Caps the requests in flight of this fetcher by `limit`, across simultaneous executions.
+    ///
+    /// Attach clones of the same limit to cap several fetchers together.
+    pub fn with_concurrency_limit(mut self, limit: ConcurrencyLimit) -> Self {
+        self.concurrency = Some(limit);
+        self
+    }
+
+    /// Sets the default retry policy of executions, [`RetryPolicy::never`] by default.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The number of clients built so far, reused by every later execution.
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// The policy sending a request of the fetcher with `retry`, without rotating tokens.
+    fn send_policy<'p>(&'p self, retry: &'p RetryPolicy) -> SendPolicy<'p> {
+        SendPolicy {
+            retry,
+            limiter: self.limiter.as_ref(),
+            concurrency: self.concurrency.as_ref(),
+            tokens: None,
+        }
+    }
+}
+
+impl<A, R> Fetcher<A, R>
+where
+    A: Endpoint<R> + ActionEndpoint,
+{
+    /// Performs a write action, e.g. posting a hole, returning what the backend returns.
+    ///
+    /// Writes are not idempotent, so an action is attempted exactly once regardless of the
+    /// retry policy. The rate limiter and the concurrency limit still apply.
+    pub async fn perform<T>(&self, action: &T) -> Result<T::Output, FetcherError>
+    where
+        T: Action,
+    {
+        let mut url = self.api.locate_action(action)?;
+        let client = self.clients.client()?;
+        let never = RetryPolicy::never();
+        let (result, _) = send_with_policy(&mut url, self.send_policy(&never), |url| async {
+            let response = action
+                .body()
+                .attach(client.post(url))
+                .map_err(FetcherError::RequestFail)?
+                .send()
+                .await
+                .map_err(FetcherError::RequestFail)?;
+            Ok(read_action::<T>(response).await?)
+        })
+        .await;
+        result
+    }
+}
+
+impl<A, R> Fetcher<A, R>
+where
+    A: Endpoint<R> + Endpoint<HoleSet>,
+{
+    /// Checks whether the backend accepts the user token, by fetching the attention list once.
+    ///
+    /// With a [`TokenPool`], this checks the token the pool hands out next, see
+    /// [`Fetcher::validate_token_for`] to check a given one.
+    pub async fn validate_token(&self) -> Result<(), CredentialError> {
+        let url = Endpoint::<HoleSet>::locate(&self.api, &FetchAttention)?;
+        self.probe_token(url).await
+    }
+
+    /// Checks whether the backend accepts `token`, by fetching the attention list once.
+    pub async fn validate_token_for(&self, token: &str) -> Result<(), CredentialError> {
+        let url = Endpoint::<HoleSet>::locate(&self.api, &FetchAttention)?;
+        self.probe_token(with_user_token(&url, token)).await
+    }
+
+    /// Fetches the attention list from `url` without rotating its token.
+    async fn probe_token(&self, mut url: Url) -> Result<(), CredentialError> {
+        let probe = |e: FetcherError| CredentialError::Probe(Box::new(e));
+        let client = self.clients.client().map_err(probe)?;
+        let policy = self.send_policy(&self.retry);
+        let (result, _) = send_with_policy(&mut url, policy, |url| async {
+            let response = client
+                .get(url)
+                .send()
+                .await
+                .map_err(FetcherError::RequestFail)?;
+            Ok(HoleSet::parse(response).await?)
+        })
+        .await;
+        match result {
+            Ok(_) => Ok(()),
+            Err(FetcherError::ParseResourceFail(e)) if e.is_token_expired() => {
+                Err(CredentialError::TokenExpired {
+                    msg: e.msg().map(String::from),
+                })
+            }
+            Err(e) => Err(probe(e)),
+        }
+    }
+}
+
+impl<A, R> Fetcher<A, R>
+where
+    A: Endpoint<R>,
+    R: Resource,
+{
+    pub fn fetch<'fch, 'lct>(
+        &'fch mut self,
+        resource: &'lct dyn Location<R>,
+    ) -> FetcherExecutor<'fch, 'lct, A, R> {
+        let swarm = resource.default_swarm();
+        let retry = self.retry.clone();
+        FetcherExecutor {
+            fetcher: self,
+            location: resource,
+            swarm,
+            strictness: Strictness::default(),
+            retry,
+            until: None,
+            merge: None,
+        }
+    }
+}
+
+impl<R> Default for Fetcher<API, R>
+where
+    API: Endpoint<R>,
+{
+    /// Try to return a new default [`Fetcher`] instance.
+    ///
+    /// This default instance uses:
+    ///
+    /// - Default [`API`] instance
+    ///     - base URL: `https://pkuhelper.pku.edu.cn/services/pkuhole/api.php`
+    ///     - token: `WOODPECKER_USER_TOKEN` provided in env vars
+    /// - A [`reqwest::Client`] instance with
+    ///     - referer: `https://pkuhelper.pku.edu.cn/hole/`
+    ///     - user agent: `Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/80.0.3987.149 Safari/537.36`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WOODPECKER_USER_TOKEN` is not found in env vars, see [`Fetcher::try_default`].
+    fn default() -> Self {
+        Fetcher::new(API::default())
+    }
+}
+
+impl<R> Fetcher<API, R>
+where
+    API: Endpoint<R>,
+{
+    /// Returns a new default [`Fetcher`] instance like [`Fetcher::default`], or an error instead
+    /// of panicking.
+    pub fn try_default() -> Result<Self, FetcherError> {
+        Ok(Fetcher::new(API::try_default()?))
+    }
+}
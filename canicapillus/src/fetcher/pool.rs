@@ -0,0 +1,42 @@
+use std::sync::Mutex;
+
+use reqwest::Client;
+
+use super::{FetcherClientBuilder, FetcherError};
+
+/// Clients kept by a [`Fetcher`](super::Fetcher) across executions, built on demand.
+///
+/// Every client keeps its own idle connections alive, so later executions skip connecting.
+/// The pool grows to the largest number of clients an execution asks for.
+pub(crate) struct ClientPool {
+    builder: Box<dyn FetcherClientBuilder>,
+    clients: Mutex<Vec<Client>>,
+}
+
+impl ClientPool {
+    pub(crate) fn new(builder: Box<dyn FetcherClientBuilder>) -> Self {
+        ClientPool {
+            builder,
+            clients: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns `count` clients, building the missing ones.
+    pub(crate) fn take(&self, count: usize) -> Result<Vec<Client>, FetcherError> {
+        let mut clients = self.clients.lock().unwrap();
+        while clients.len() < count {
+            clients.push(self.builder.build()?);
+        }
+        Ok(clients[..count].to_vec())
+    }
+
+    /// Returns the first client, building it if missing.
+    pub(crate) fn client(&self) -> Result<Client, FetcherError> {
+        Ok(self.take(1)?.remove(0))
+    }
+
+    /// The number of clients built so far.
+    pub(crate) fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+}
@@ -0,0 +1,143 @@
+use url::Url;
+
+use super::{Attempted, FetcherError};
+use crate::common::{PageMeta, SwarmError};
+
+/// A fetched page.
+#[derive(Debug)]
+pub struct Page<R> {
+    /// The page number, starting from 1.
+    pub page: usize,
+    /// The requested URL, `None` if the page cannot be dispatched.
+    pub url: Option<Url>,
+    /// The number of requests made for the page, 0 if the page cannot be dispatched.
+    pub attempts: usize,
+    /// The metadata of the page, empty if the page fails.
+    pub meta: PageMeta,
+    pub result: Result<R, FetcherError>,
+}
+
+impl<R> Page<R> {
+    pub(crate) fn undispatched(page: usize, error: SwarmError) -> Self {
+        Page::unrequested(page, None, error.into())
+    }
+
+    /// A page failing before any request, e.g. without a client to request it.
+    pub(crate) fn unrequested(page: usize, url: Option<Url>, error: FetcherError) -> Self {
+        Page {
+            page,
+            url,
+            attempts: 0,
+            meta: PageMeta::default(),
+            result: Err(error),
+        }
+    }
+
+    pub(crate) fn fetched(page: usize, url: Url, (result, attempts): Attempted<R>) -> Self {
+        let (result, meta) = match result {
+            Ok((resource, meta)) => (Ok(resource), meta),
+            Err(e) => (Err(e), PageMeta::default()),
+        };
+        Page {
+            page,
+            url: Some(url),
+            attempts,
+            meta,
+            result,
+        }
+    }
+}
+
+/// The outcome of fetching one page.
+#[derive(Debug)]
+pub struct PageReport {
+    /// The page number, starting from 1.
+    pub page: usize,
+    /// The requested URL, `None` if the page cannot be dispatched.
+    pub url: Option<Url>,
+    /// The number of requests made for the page, 0 if the page cannot be dispatched.
+    pub attempts: usize,
+    /// The metadata of the page, empty if the page fails.
+    pub meta: PageMeta,
+    /// The number of entries on the page, or why the page fails.
+    pub result: Result<usize, FetcherError>,
+}
+
+impl PageReport {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// The merged resource of an execution, together with a report of every page.
+#[derive(Debug)]
+pub struct SwarmOutcome<R> {
+    pub resource: R,
+    /// Reports ordered by page number.
+    pub reports: Vec<PageReport>,
+}
+
+impl<R> SwarmOutcome<R> {
+    /// The number of pages which succeed.
+    pub fn succeeded(&self) -> usize {
+        self.reports.iter().filter(|r| r.is_ok()).count()
+    }
+
+    /// Whether every page succeeds.
+    pub fn is_complete(&self) -> bool {
+        self.reports.iter().all(PageReport::is_ok)
+    }
+
+    /// Reports of the failing pages.
+    pub fn failures(&self) -> impl Iterator<Item = &PageReport> {
+        self.reports.iter().filter(|r| !r.is_ok())
+    }
+}
+
+/// How an execution treats failing pages.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Strictness {
+    /// Stops on the first failing page and fails with [`FetcherError::PageFail`].
+    FailFast,
+    /// Succeeds with whatever pages succeed.
+    #[default]
+    BestEffort,
+    /// Fails with [`FetcherError::SwarmIncomplete`] unless at least this ratio of pages succeeds.
+    MinSuccessRatio(f64),
+}
+
+impl Strictness {
+    /// Whether the execution should stop after `report`.
+    pub(crate) fn gives_up(&self, report: &PageReport) -> bool {
+        matches!(self, Strictness::FailFast) && !report.is_ok()
+    }
+
+    /// Judges a finished execution, `total` being the number of pages requested.
+    pub(crate) fn judge<R>(
+        &self,
+        mut outcome: SwarmOutcome<R>,
+        total: usize,
+    ) -> Result<SwarmOutcome<R>, FetcherError> {
+        outcome.reports.sort_by_key(|r| r.page);
+        let succeeded = outcome.succeeded();
+        match *self {
+            Strictness::FailFast if !outcome.is_complete() => {
+                let report = outcome.reports.into_iter().find(|r| !r.is_ok()).unwrap();
+                Err(FetcherError::PageFail {
+                    page: report.page,
+                    source: Box::new(report.result.unwrap_err()),
+                })
+            }
+            Strictness::MinSuccessRatio(ratio)
+                if total > 0 && (succeeded as f64) < ratio * total as f64 =>
+            {
+                Err(FetcherError::SwarmIncomplete {
+                    succeeded,
+                    total,
+                    reports: outcome.reports,
+                })
+            }
+            _ => Ok(outcome),
+        }
+    }
+}
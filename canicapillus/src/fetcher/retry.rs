@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::StatusCode;
+
+use super::FetcherError;
+use crate::common::ParseResourceError;
+
+/// How long to wait before retrying a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    /// Waits the same duration after every attempt.
+    Constant(Duration),
+    /// Waits `initial`, then `factor` times longer after every attempt, but never longer than `max`.
+    Exponential {
+        initial: Duration,
+        factor: f64,
+        max: Duration,
+    },
+}
+
+/// How to randomize a backoff duration, so that clients failing together do not retry together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Jitter {
+    /// Waits exactly the backoff duration.
+    #[default]
+    None,
+    /// Waits a random duration between zero and the backoff duration.
+    Full,
+    /// Waits at least half of the backoff duration, plus a random share of the other half.
+    Equal,
+}
+
+/// When and how to retry a failed request.
+///
+/// A retry policy applies to every page of an execution, including every page of a swarm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximal number of attempts per page, including the first one.
+    pub max_attempts: usize,
+    pub backoff: Backoff,
+    pub jitter: Jitter,
+    /// HTTP statuses worth retrying.
+    pub statuses: Vec<StatusCode>,
+    /// Whether to retry requests failing to connect, to send or to receive, including timeouts.
+    pub transport: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Returns [`RetryPolicy::never`].
+    fn default() -> Self {
+        RetryPolicy::never()
+    }
+}
+
+impl RetryPolicy {
+    /// Returns a policy making exactly one attempt.
+    pub fn never() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            backoff: Backoff::Constant(Duration::ZERO),
+            jitter: Jitter::None,
+            statuses: Vec::new(),
+            transport: false,
+        }
+    }
+
+    /// Returns a policy making at most `max_attempts` attempts.
+    ///
+    /// It retries transport failures and HTTP 429, 500, 502, 503 and 504,
+    /// waiting 200ms, 400ms, 800ms and so on up to 10s, with full jitter.
+    pub fn exponential(max_attempts: usize) -> Self {
+        RetryPolicy {
+            max_attempts,
+            backoff: Backoff::Exponential {
+                initial: Duration::from_millis(200),
+                factor: 2.0,
+                max: Duration::from_secs(10),
+            },
+            jitter: Jitter::Full,
+            statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            transport: true,
+        }
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn statuses(mut self, statuses: Vec<StatusCode>) -> Self {
+        self.statuses = statuses;
+        self
+    }
+
+    pub fn transport(mut self, transport: bool) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Whether a request failing with `error` is worth another attempt.
+    pub fn is_retryable(&self, error: &FetcherError) -> bool {
+        let transport = |e: &reqwest::Error| {
+            self.transport && (e.is_timeout() || e.is_connect() || e.is_request() || e.is_body())
+        };
+        match error {
+            FetcherError::RequestFail(e) => transport(e),
+            FetcherError::ParseResourceFail(ParseResourceError::Body(e)) => transport(e),
+            FetcherError::ParseResourceFail(ParseResourceError::Status { status, .. }) => {
+                self.statuses.contains(status)
+            }
+            _ => false,
+        }
+    }
+
+    /// The duration to wait after the `attempt`-th attempt fails, counting from 1.
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let backoff = match self.backoff {
+            Backoff::Constant(duration) => duration,
+            Backoff::Exponential {
+                initial,
+                factor,
+                max,
+            } => {
+                let exponent = attempt.saturating_sub(1).min(i32::MAX as usize) as i32;
+                let secs = initial.as_secs_f64() * factor.powi(exponent);
+                Duration::from_secs_f64(secs.min(max.as_secs_f64()))
+            }
+        };
+        match self.jitter {
+            Jitter::None => backoff,
+            Jitter::Full => backoff.mul_f64(rand::thread_rng().gen_range(0.0..=1.0)),
+            Jitter::Equal => {
+                backoff / 2 + (backoff / 2).mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+            }
+        }
+    }
+}
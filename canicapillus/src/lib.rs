@@ -1,7 +1,10 @@
 pub mod api;
 pub mod common;
+pub mod credential;
 pub mod fetcher;
 pub mod prebuilt;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 use std::collections::BTreeSet;
 
 pub(crate) use major::hole::{self, HoleEntry, reply::ReplyEntry};
@@ -11,69 +14,1261 @@ pub type ReplySet = BTreeSet<ReplyEntry>;
 
 #[cfg(test)]
 mod tests {
-    use std::{fs::File, io::Write};
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use chrono::{TimeZone, Utc};
+    use futures::StreamExt;
+    use reqwest::{
+        header::{HeaderName, HeaderValue, REFERER, USER_AGENT},
+        Proxy, StatusCode,
+    };
+    use sha2::{Digest, Sha256};
+    use url::Url;
 
     use crate::{
-        fetcher,
-        hole::{HoleID},
-        HoleSet, ReplySet,
+        api::API,
+        common::{
+            Location, MergeResource, MergeStrategy, ParseResourceError, Swarm, SwarmError,
+            LOGIN_REQUIRED_CODE,
+        },
+        credential::{
+            CredentialError, EnvToken, FileToken, ProviderChain, TokenPool, TokenProvider,
+        },
+        fetcher::{
+            Backoff, ConcurrencyLimit, Fetcher, FetcherError, MediaCache, MediaError, Quota,
+            RateLimiter, RetryPolicy, Strictness,
+        },
+        hole::{conversation::Conversation, Hole, HoleID, HoleKind},
         prebuilt::*,
+        testing::{Fixture, FixtureHole, MockServer, FIXTURE_TIMESTAMP, MOCK_USER_TOKEN},
+        HoleEntry, HoleSet, ReplySet,
     };
 
+    /// Fetches a hole on every page, to exercise partially failing swarms.
+    struct FetchEach(Vec<HoleID>);
+
+    impl Location<HoleSet> for FetchEach {
+        fn locate(&self, mut url: Url) -> Url {
+            url.query_pairs_mut().append_pair("action", "getone");
+            url
+        }
+
+        fn dispatch(
+            &self,
+            mut url: Url,
+            _swarm: Option<&Swarm>,
+            page: usize,
+            _page_size: usize,
+        ) -> Result<Url, SwarmError> {
+            let id = self.0.get(page - 1).ok_or(SwarmError::Unsupported)?;
+            url.query_pairs_mut().append_pair("pid", &String::from(*id));
+            Ok(url)
+        }
+    }
+
     #[tokio::test]
-    #[allow(unused_must_use)] 
     async fn fetch_reply() {
-        let mut fetcher = fetcher::Fetcher::default();
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, ReplySet> = server.fetcher();
         let fetch = FetchReply {
-            hole_id: HoleID(3761702),
-        };
-        let result = fetcher
-                .fetch(&fetch)
-                .execute()
-                .await;
-
-        match result {
-            Ok(res) => { 
-                let string = serde_json::to_string(&res).unwrap();
-                let back = serde_json::from_str::<ReplySet>(&string).unwrap();
-                assert_eq!(res, back);
-                dbg!(back);
-             },
-            Err(err) => { dbg!(err); }
+            hole_id: HoleID(1003),
         };
+        let result = fetcher.fetch(&fetch).execute().await.unwrap();
+        assert_eq!(result.len(), 3);
+        assert!(result.iter().all(|r| r.entry.hole == HoleID(1003)));
+        assert!(result.iter().next().unwrap().entry.dz);
+
+        let string = serde_json::to_string(&result).unwrap();
+        let back = serde_json::from_str::<ReplySet>(&string).unwrap();
+        assert_eq!(result, back);
     }
 
     #[tokio::test]
     async fn fetch_hole() {
-        let mut fetcher = fetcher::Fetcher::default();
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
         let fetch = FetchFeed;
+        let result = fetcher.fetch(&fetch).execute().await.unwrap();
+        // the default swarm of the feed requests 4 pages of 30 holes
+        assert_eq!(result.len(), 100);
+        assert_eq!(server.requests().len(), 4);
+
+        let string = serde_json::to_string(&result).unwrap();
+        let back = serde_json::from_str::<HoleSet>(&string).unwrap();
+        assert_eq!(result, back);
+    }
+
+    #[tokio::test]
+    async fn fetch_single_hole() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let fetch = FetchSingle { id: HoleID(1025) };
+        let result = fetcher.fetch(&fetch).execute().await.unwrap();
+        assert_eq!(result.len(), 1);
+        let hole = &result.iter().next().unwrap().entry;
+        assert_eq!(hole.id, HoleID(1025));
+        assert_eq!(
+            hole.kind,
+            HoleKind::Image {
+                url: String::from("1025.jpeg")
+            }
+        );
+
+        let string = serde_json::to_string(&result).unwrap();
+        let back = serde_json::from_str::<HoleSet>(&string).unwrap();
+        assert_eq!(result, back);
+    }
+
+    #[tokio::test]
+    async fn fetch_search_sequential() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let fetch = FetchSearch {
+            keyword: String::from("woodpecker"),
+        };
         let result = fetcher
             .fetch(&fetch)
+            .swarm(Some(Swarm::Sequential {
+                count: 3,
+                page_size: 10,
+            }))
             .execute()
             .await
             .unwrap();
-        let string = serde_json::to_string(&result).unwrap();
-        let back = serde_json::from_str::<HoleSet>(&string).unwrap();
-        assert_eq!(result, back);
-        let mut file = File::create("holes.test.json").unwrap();
-        file.write_all(string.as_bytes()).unwrap();
-        dbg!(string);
-        // dbg!(result);
+        assert_eq!(result.len(), 30);
+        assert!(result.iter().all(|h| h.entry.text.contains("woodpecker")));
     }
 
     #[tokio::test]
-    async fn fetch_single_hole() {
-        let mut fetcher = fetcher::Fetcher::default();
-        let fetch = FetchSingle { id: HoleID(3558999) };
+    async fn fetch_attention() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let result = fetcher.fetch(&FetchAttention).execute().await.unwrap();
+        let ids = result.iter().map(|h| h.entry.id).collect::<Vec<_>>();
+        assert_eq!(ids, [HoleID(1025), HoleID(1050), HoleID(1075), HoleID(1100)]);
+    }
+
+    #[tokio::test]
+    async fn report_backend_error() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> =
+            Fetcher::new(API::new(&server.base(), None, "expired-token"));
+        let fetch = FetchSingle { id: HoleID(1025) };
+        let error = fetcher.fetch(&fetch).execute().await.unwrap_err();
+        match error {
+            FetcherError::ParseResourceFail(e @ ParseResourceError::Backend { .. }) => {
+                assert_eq!(e.status().map(|s| s.as_u16()), Some(200));
+                assert_eq!(e.code(), Some(-1));
+                assert_eq!(e.msg(), Some("请先登录"));
+                assert!(e.snippet().unwrap().contains("请先登录"));
+                assert!(e.is_token_expired());
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+
+        let backend = |code: i64, msg: &str| ParseResourceError::Backend {
+            status: StatusCode::OK,
+            code,
+            msg: Some(String::from(msg)),
+            snippet: String::new(),
+        };
+        assert!(backend(LOGIN_REQUIRED_CODE, "Log in again.").is_token_expired());
+        assert!(!backend(-1, "没有这条树洞").is_token_expired());
+    }
+
+    #[tokio::test]
+    async fn report_malformed_resource() {
+        let mut hole = FixtureHole::text(1, "A hole of an unknown kind.");
+        hole.kind = String::from("video");
+        let server = MockServer::start(Fixture::empty().with_hole(hole)).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let error = fetcher
+            .fetch(&FetchSingle { id: HoleID(1) })
+            .execute()
+            .await
+            .unwrap_err();
+        match error {
+            FetcherError::ParseResourceFail(ParseResourceError::Malformed {
+                path, line, snippet, ..
+            }) => {
+                assert!(path.starts_with("data"), "{}", path);
+                assert_eq!(line, 1);
+                assert!(snippet.contains("video"));
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn report_failing_pages() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let fetch = FetchEach(vec![HoleID(1001), HoleID(1), HoleID(1002)]);
+        let outcome = fetcher
+            .fetch(&fetch)
+            .swarm(Some(Swarm::Concurrent {
+                count: 4,
+                page_size: 1,
+                workers: None,
+            }))
+            .execute_with_report()
+            .await
+            .unwrap();
+        assert_eq!(outcome.resource.len(), 2);
+        assert_eq!(outcome.succeeded(), 2);
+        let pages = outcome.reports.iter().map(|r| r.page).collect::<Vec<_>>();
+        assert_eq!(pages, [1, 2, 3, 4]);
+        let failures = outcome.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 2);
+        assert!(matches!(
+            failures[0].result,
+            Err(FetcherError::ParseResourceFail(
+                ParseResourceError::Backend { .. }
+            ))
+        ));
+        assert!(failures[1].url.is_none());
+    }
+
+    #[tokio::test]
+    async fn fail_fast() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let fetch = FetchEach(vec![HoleID(1001), HoleID(1), HoleID(1002)]);
+        let error = fetcher
+            .fetch(&fetch)
+            .swarm(Some(Swarm::Sequential {
+                count: 3,
+                page_size: 1,
+            }))
+            .strictness(Strictness::FailFast)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, FetcherError::PageFail { page: 2, .. }));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn require_success_ratio() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let fetch = FetchEach(vec![HoleID(1001), HoleID(1), HoleID(1002)]);
+        let swarm = Swarm::Concurrent {
+            count: 3,
+            page_size: 1,
+            workers: None,
+        };
+        let error = fetcher
+            .fetch(&fetch)
+            .swarm(Some(swarm))
+            .strictness(Strictness::MinSuccessRatio(0.9))
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            FetcherError::SwarmIncomplete {
+                succeeded: 2,
+                total: 3,
+                ..
+            }
+        ));
+
+        let swarm = Swarm::Concurrent {
+            count: 3,
+            page_size: 1,
+            workers: None,
+        };
         let result = fetcher
             .fetch(&fetch)
+            .swarm(Some(swarm))
+            .strictness(Strictness::MinSuccessRatio(0.5))
             .execute()
             .await
             .unwrap();
-        let string = serde_json::to_string(&result).unwrap();
-        let back = serde_json::from_str::<HoleSet>(&string).unwrap();
-        assert_eq!(result, back);
-        dbg!(string);
-        // dbg!(result);
+        assert_eq!(result.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retry_transient_failures() {
+        let server = MockServer::start(Fixture::default()).await;
+        let retry =
+            RetryPolicy::exponential(3).backoff(Backoff::Constant(Duration::from_millis(1)));
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher().with_retry(retry);
+        let fetch = FetchSingle { id: HoleID(1025) };
+
+        server.fail_next(2, StatusCode::BAD_GATEWAY);
+        let outcome = fetcher.fetch(&fetch).execute_with_report().await.unwrap();
+        assert_eq!(outcome.resource.len(), 1);
+        assert_eq!(outcome.reports[0].attempts, 3);
+
+        server.fail_next(3, StatusCode::BAD_GATEWAY);
+        let error = fetcher.fetch(&fetch).execute().await.unwrap_err();
+        match error {
+            FetcherError::ParseResourceFail(e @ ParseResourceError::Status { .. }) => {
+                assert_eq!(e.status(), Some(StatusCode::BAD_GATEWAY));
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+        assert_eq!(server.requests().len(), 6);
+
+        server.fail_next(1, StatusCode::SERVICE_UNAVAILABLE);
+        let outcome = fetcher
+            .fetch(&fetch)
+            .retry(RetryPolicy::never())
+            .execute_with_report()
+            .await;
+        assert!(outcome.is_err());
+        assert_eq!(server.requests().len(), 7);
+    }
+
+    #[tokio::test]
+    async fn retry_swarm_pages() {
+        let server = MockServer::start(Fixture::default()).await;
+        let retry =
+            RetryPolicy::exponential(3).backoff(Backoff::Constant(Duration::from_millis(1)));
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher().with_retry(retry);
+
+        server.fail_next(2, StatusCode::TOO_MANY_REQUESTS);
+        let outcome = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Sequential {
+                count: 4,
+                page_size: 30,
+            }))
+            .execute_with_report()
+            .await
+            .unwrap();
+        assert!(outcome.is_complete());
+        assert_eq!(outcome.resource.len(), 100);
+        let attempts = outcome.reports.iter().map(|r| r.attempts).sum::<usize>();
+        assert_eq!(attempts, 6);
+
+        server.fail_next(1, StatusCode::TOO_MANY_REQUESTS);
+        let outcome = fetcher
+            .fetch(&FetchFeed)
+            .execute_with_report()
+            .await
+            .unwrap();
+        assert!(outcome.is_complete());
+        assert_eq!(outcome.resource.len(), 100);
+    }
+
+    #[tokio::test]
+    async fn limit_request_rate() {
+        let server = MockServer::start(Fixture::default()).await;
+        let period = Duration::from_millis(50);
+        let limiter = RateLimiter::unlimited().action("getlist", Quota { burst: 1, period });
+        let mut first: Fetcher<API, HoleSet> = server.fetcher().with_rate_limiter(limiter.clone());
+        let mut second: Fetcher<API, HoleSet> = server.fetcher().with_rate_limiter(limiter);
+        let swarm = || {
+            Some(Swarm::Concurrent {
+                count: 2,
+                page_size: 30,
+                workers: None,
+            })
+        };
+
+        // the first page takes the only token, the others wait for one period each
+        let mut a = first.fetch(&FetchFeed).swarm(swarm());
+        let mut b = second.fetch(&FetchFeed).swarm(swarm());
+        let start = Instant::now();
+        let (a, b) = tokio::join!(a.execute(), b.execute());
+        assert_eq!(a.unwrap().len(), 60);
+        assert_eq!(b.unwrap().len(), 60);
+        assert!(start.elapsed() >= period * 3);
+
+        // other actions are not limited
+        let mut replies: Fetcher<API, ReplySet> = server
+            .fetcher()
+            .with_rate_limiter(RateLimiter::unlimited().action("getlist", Quota::per_minute(1)));
+        let fetch = FetchReply {
+            hole_id: HoleID(1003),
+        };
+        assert_eq!(replies.fetch(&fetch).execute().await.unwrap().len(), 3);
+        assert_eq!(replies.fetch(&fetch).execute().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn stream_pages() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+
+        let mut pages = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Concurrent {
+                count: 5,
+                page_size: 30,
+                workers: None,
+            }))
+            .stream()
+            .map(|page| (page.page, page.result.unwrap().len()))
+            .collect::<Vec<_>>()
+            .await;
+        pages.sort();
+        assert_eq!(pages, [(1, 30), (2, 30), (3, 30), (4, 10), (5, 0)]);
+
+        let fetch = FetchEach(vec![HoleID(1001), HoleID(1), HoleID(1002)]);
+        let entries = fetcher
+            .fetch(&fetch)
+            .swarm(Some(Swarm::Sequential {
+                count: 3,
+                page_size: 1,
+            }))
+            .stream_entries()
+            .map(|entry| entry.map(|hole| hole.entry.id).ok())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(entries, [Some(HoleID(1001)), None, Some(HoleID(1002))]);
+    }
+
+    #[tokio::test]
+    async fn exhaust_pages() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+
+        let outcome = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Exhaustive {
+                page_size: 30,
+                max_pages: None,
+            }))
+            .execute_with_report()
+            .await
+            .unwrap();
+        assert_eq!(outcome.resource.len(), 100);
+        assert_eq!(outcome.reports.len(), 4);
+        assert_eq!(server.requests().len(), 4);
+
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let holes = fetcher
+            .fetch(&FetchSearch {
+                keyword: String::from("woodpecker"),
+            })
+            .swarm(Some(Swarm::Exhaustive {
+                page_size: 10,
+                max_pages: Some(3),
+            }))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(holes.len(), 30);
+
+        let holes = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Exhaustive {
+                page_size: 30,
+                max_pages: None,
+            }))
+            .until(|holes: &HoleSet| holes.iter().any(|hole| hole.entry.id < HoleID(1050)))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(holes.len(), 60);
+        assert_eq!(server.requests().len(), 9);
+
+        let holes = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Exhaustive {
+                page_size: 0,
+                max_pages: None,
+            }))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(holes.len(), 100);
+        assert_eq!(server.requests().len(), 14);
+    }
+
+    #[tokio::test]
+    async fn sync_feed() {
+        let server = MockServer::start(Fixture::generate(1001..=1060)).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        let known = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Exhaustive {
+                page_size: 30,
+                max_pages: None,
+            }))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(known.len(), 60);
+
+        let mut fixture = Fixture::default();
+        fixture
+            .holes
+            .iter_mut()
+            .find(|h| h.pid == 1060)
+            .unwrap()
+            .likenum = 42;
+        let server = MockServer::start(fixture).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+
+        let mut sync = FeedSync::from_known(&known);
+        let delta = sync.sync(&mut fetcher).await.unwrap();
+        assert_eq!(delta.fresh.len(), 40);
+        assert_eq!(delta.fresh.iter().next().unwrap().entry.id, HoleID(1061));
+        assert_eq!(
+            delta.updated.into_iter().collect::<Vec<_>>(),
+            [(
+                HoleID(1060),
+                Counters {
+                    reply: 0,
+                    likenum: 42
+                }
+            )]
+        );
+        assert_eq!(sync.latest(), HoleID(1100));
+        assert_eq!(server.requests().len(), 2);
+
+        let delta = sync.sync(&mut fetcher).await.unwrap();
+        assert!(delta.fresh.is_empty() && delta.updated.is_empty());
+        assert_eq!(server.requests().len(), 3);
+
+        let delta = FeedSync::since(HoleID(1090))
+            .sync(&mut fetcher)
+            .await
+            .unwrap();
+        assert_eq!(delta.fresh.len(), 10);
+        assert_eq!(delta.updated.len(), 20);
+        assert_eq!(delta.gap, None);
+
+        let mut sync = FeedSync::since(HoleID(1001)).max_pages(1);
+        let delta = sync.sync(&mut fetcher).await.unwrap();
+        assert_eq!(delta.fresh.len(), 30);
+        assert_eq!(delta.gap, Some(HoleID(1002)..HoleID(1071)));
+        assert_eq!(sync.latest(), HoleID(1001));
+        let mut sync = sync.max_pages(4);
+        let delta = sync.sync(&mut fetcher).await.unwrap();
+        assert_eq!(delta.fresh.len(), 99);
+        assert_eq!(delta.gap, None);
+        assert_eq!(sync.latest(), HoleID(1100));
+    }
+
+    #[tokio::test]
+    async fn merge_strategies() {
+        let copy = |likenum: usize, reply: usize, snapshot: i64| HoleEntry {
+            entry: Hole {
+                id: HoleID(1),
+                text: format!("{} likes.", likenum),
+                kind: HoleKind::Text,
+                timestamp: Utc.timestamp_opt(1_600_000_000, 0).unwrap(),
+                reply,
+                likenum,
+                tag: None,
+            },
+            snapshot: Utc.timestamp_opt(snapshot, 0).unwrap(),
+        };
+        // pages in page order, the second page holding an older snapshot
+        let pages = || {
+            [
+                HoleSet::from([copy(1, 5, 2000)]),
+                HoleSet::from([copy(9, 0, 1000)]),
+                HoleSet::from([copy(3, 1, 2000)]),
+            ]
+        };
+        let merge = |strategy: MergeStrategy<HoleEntry>| {
+            let merged = pages().into_iter().fold(HoleSet::new(), |lhs, rhs| {
+                HoleSet::merge_with(lhs, rhs, &strategy).unwrap()
+            });
+            assert_eq!(merged.len(), 1);
+            let hole = merged.into_iter().next().unwrap().entry;
+            (hole.likenum, hole.reply)
+        };
+        assert_eq!(merge(MergeStrategy::FirstWins), (1, 5));
+        assert_eq!(merge(MergeStrategy::LastWins), (3, 1));
+        assert_eq!(merge(MergeStrategy::NewestSnapshot), (3, 1));
+        assert_eq!(merge(MergeStrategy::MaxCounters), (9, 5));
+        assert_eq!(
+            merge(MergeStrategy::custom(|mut former: HoleEntry, latter| {
+                former.entry.likenum += latter.entry.likenum;
+                former
+            })),
+            (13, 5)
+        );
+
+        /// Fetches the hole 1003 on the first two pages, and searches for it on the third,
+        /// whose copy is snapshot now as a search page has no timestamp.
+        struct FetchCopies;
+
+        impl Location<HoleSet> for FetchCopies {
+            fn locate(&self, url: Url) -> Url {
+                url
+            }
+
+            fn dispatch(
+                &self,
+                url: Url,
+                swarm: Option<&Swarm>,
+                page: usize,
+                _page_size: usize,
+            ) -> Result<Url, SwarmError> {
+                if page < 3 {
+                    return Ok(FetchSingle { id: HoleID(1003) }.locate(url));
+                }
+                let search = FetchSearch {
+                    keyword: String::from("Hole 1003."),
+                };
+                search.dispatch(search.locate(url), swarm, 1, 1)
+            }
+        }
+
+        let server = MockServer::start(Fixture::default()).await;
+        let fixture_snapshot = Utc.timestamp_opt(FIXTURE_TIMESTAMP, 0).unwrap();
+        let swarm = Swarm::Concurrent {
+            count: 3,
+            page_size: 1,
+            workers: None,
+        };
+        for (strategy, from_search) in [
+            (MergeStrategy::FirstWins, false),
+            (MergeStrategy::LastWins, true),
+            (MergeStrategy::NewestSnapshot, true),
+        ] {
+            let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+            let holes = fetcher
+                .fetch(&FetchCopies)
+                .swarm(Some(swarm))
+                .merge(strategy)
+                .execute()
+                .await
+                .unwrap();
+            assert_eq!(holes.len(), 1);
+            let hole = holes.into_iter().next().unwrap();
+            assert_eq!(hole.entry.id, HoleID(1003));
+            assert_eq!(hole.snapshot != fixture_snapshot, from_search);
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_thread() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, Thread> = server.fetcher();
+
+        let thread = fetcher
+            .fetch(&FetchThread { id: HoleID(1075) })
+            .execute()
+            .await
+            .unwrap();
+        let hole = thread.hole.unwrap();
+        assert_eq!(hole.entry.id, HoleID(1075));
+        assert_eq!(hole.entry.reply, 3);
+        assert_eq!(
+            thread
+                .replies
+                .iter()
+                .map(|r| r.entry.id.0)
+                .collect::<Vec<_>>(),
+            [107500, 107501, 107502]
+        );
+        assert_eq!(thread.attention, Some(true));
+        assert_eq!(server.requests().len(), 2);
+
+        let result = fetcher
+            .fetch(&FetchThread { id: HoleID(1) })
+            .strictness(Strictness::FailFast)
+            .execute()
+            .await;
+        assert!(matches!(result, Err(FetcherError::PageFail { .. })));
+    }
+
+    #[tokio::test]
+    async fn crawl_replies() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut holes: Fetcher<API, HoleSet> = server.fetcher();
+        let mut replies: Fetcher<API, ReplySet> = server.fetcher();
+
+        let feed = holes.fetch(&FetchFeed).swarm(None).execute().await.unwrap();
+        let crawl = FetchReplies::new(
+            feed.iter()
+                .map(|hole| hole.entry.id)
+                .chain([HoleID(1), HoleID(1075)]),
+        )
+        .concurrency(4);
+        assert_eq!(crawl.ids().len(), 31);
+
+        let crawled = crawl.crawl(&mut replies).await;
+        assert_eq!(crawled.replies.len(), 30);
+        assert_eq!(crawled.replies[&HoleID(1075)].len(), 3);
+        assert!(crawled
+            .replies
+            .iter()
+            .all(|(id, replies)| replies.iter().all(|reply| reply.entry.hole == *id)));
+        assert!(!crawled.is_complete());
+        assert!(matches!(
+            crawled.failures[&HoleID(1)],
+            FetcherError::ParseResourceFail(ParseResourceError::Backend { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn crawl_citations() {
+        let mut cited = FixtureHole::text(1002, "Back to #1001, and #1.");
+        cited.replies = Fixture::generate([1002]).holes[0].replies.clone();
+        cited.replies[0].text = String::from("Also see #1003.");
+        let server = MockServer::start(
+            Fixture::default()
+                .with_hole(FixtureHole::text(1001, "See #1002 and #1004, again #1002."))
+                .with_hole(cited)
+                .with_hole(FixtureHole::text(1003, "Deeper, #1005.")),
+        )
+        .await;
+        let mut holes: Fetcher<API, HoleSet> = server.fetcher();
+        let mut replies: Fetcher<API, ReplySet> = server.fetcher();
+
+        let graph = FetchCitations::new(HoleID(1001))
+            .max_depth(2)
+            .crawl(&mut holes, Some(&mut replies))
+            .await;
+        let ids = graph.nodes.keys().map(|id| id.0).collect::<Vec<_>>();
+        assert_eq!(ids, [1001, 1002, 1003, 1004]);
+        assert_eq!(graph.nodes[&HoleID(1003)].depth, 2);
+        assert_eq!(
+            graph.nodes[&HoleID(1002)].replies.as_ref().unwrap().len(),
+            2
+        );
+        let cites = graph.cites(HoleID(1002)).map(|id| id.0).collect::<Vec<_>>();
+        assert_eq!(cites, [1, 1001, 1003]);
+        assert_eq!(
+            graph.cited_by(HoleID(1002)).collect::<Vec<_>>(),
+            [HoleID(1001)]
+        );
+        assert!(graph.has_cycle());
+        assert!(graph.failures.contains_key(&HoleID(1)));
+        assert!(graph.missing.is_empty());
+        assert_eq!(graph.truncated.iter().collect::<Vec<_>>(), [&HoleID(1005)]);
+        assert!(!graph.is_complete());
+
+        let graph = FetchCitations::new(HoleID(1001))
+            .max_holes(2)
+            .crawl::<_, API>(&mut holes, None)
+            .await;
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.nodes.values().all(|node| node.replies.is_none()));
+        let truncated = graph.truncated.iter().map(|id| id.0).collect::<Vec<_>>();
+        assert_eq!(truncated, [1, 1004]);
+        assert!(graph.is_complete());
+    }
+
+    #[tokio::test]
+    async fn download_media() {
+        let media = |pid: usize, kind: &str, url: &str| {
+            let mut hole = FixtureHole::text(pid, "A media hole.");
+            hole.kind = String::from(kind);
+            hole.url = String::from(url);
+            hole
+        };
+        let jpeg = Fixture::default().media["1005.jpeg"].clone();
+        let server = MockServer::start(
+            Fixture::default()
+                .with_hole(media(2001, "audio", "2001.mp3"))
+                .with_hole(media(2002, "image", "huge.png"))
+                .with_hole(media(2003, "image", "text.jpeg"))
+                .with_hole(media(2004, "image", "missing.jpeg"))
+                .with_hole(media(2005, "image", "copy.jpeg"))
+                .with_media("2001.mp3", b"ID3\x04\x00 an audio")
+                .with_media(
+                    "huge.png",
+                    &[b"\x89PNG\r\n\x1A\n".as_slice(), &[0; 64]].concat(),
+                )
+                .with_media("text.jpeg", b"not an image")
+                .with_media("copy.jpeg", &jpeg),
+        )
+        .await;
+        let mut holes: Fetcher<API, HoleSet> = server.fetcher();
+        let ids = [1001, 1005, 2001, 2002, 2003, 2004, 2005].map(HoleID);
+        let set = holes
+            .fetch(&FetchEach(ids.to_vec()))
+            .swarm(Some(Swarm::Concurrent {
+                count: ids.len(),
+                page_size: 1,
+                workers: None,
+            }))
+            .execute()
+            .await
+            .unwrap();
+
+        let root = std::env::temp_dir().join(format!("woodpecker-media-{}", std::process::id()));
+        let cache = MediaCache::new(&root).max_size(32);
+        let attached = holes.attach_media(set.clone(), &cache).await;
+        assert_eq!(
+            attached
+                .iter()
+                .map(|m| m.entry.entry.id)
+                .collect::<Vec<_>>(),
+            ids
+        );
+        let mut attached = attached.into_iter().map(|m| m.media);
+
+        assert!(attached.next().unwrap().unwrap().is_none());
+        let image = attached.next().unwrap().unwrap().unwrap();
+        assert_eq!(image.mime, "image/jpeg");
+        assert!(image.path.starts_with(&root));
+        assert_eq!(image.path.extension().unwrap(), "jpeg");
+        assert_eq!(image.sha256.len(), 64);
+        assert_eq!(std::fs::read(&image.path).unwrap(), jpeg);
+        let audio = attached.next().unwrap().unwrap().unwrap();
+        assert_eq!(audio.mime, "audio/mpeg");
+        assert_eq!(audio.url.path(), "/services/pkuhole/audios/2001.mp3");
+        assert!(matches!(
+            attached.next().unwrap(),
+            Err(FetcherError::MediaFail(MediaError::TooLarge {
+                limit: 32,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            attached.next().unwrap(),
+            Err(FetcherError::MediaFail(MediaError::UnsupportedType {
+                mime: None,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            attached.next().unwrap(),
+            Err(FetcherError::ParseResourceFail(
+                ParseResourceError::Status {
+                    status: StatusCode::NOT_FOUND,
+                    ..
+                }
+            ))
+        ));
+        let copy = attached.next().unwrap().unwrap().unwrap();
+        assert_eq!(copy.path, image.path);
+
+        // cached urls are not requested again
+        let requested = server.requests().len();
+        let again = holes.attach_media(set, &cache).await;
+        assert_eq!(server.requests().len(), requested + 3);
+        assert_eq!(again[1].media.as_ref().unwrap().as_ref(), Some(&image));
+
+        // concurrent writers of the same media do not clash
+        let stored =
+            futures::future::join_all((0..8).map(|_| cache.store(&image.url, &jpeg))).await;
+        assert!(stored.into_iter().all(|media| media.unwrap() == image));
+        assert_eq!(
+            cache.path_of(&image.sha256, &image.mime),
+            Some(image.path.clone())
+        );
+        assert_eq!(cache.path_of("é", &image.mime), None);
+
+        // a corrupted index is a miss rather than a panic
+        let index = root
+            .join("urls")
+            .join(format!("{:x}", Sha256::digest(image.url.as_str())));
+        std::fs::write(&index, "é image/jpeg\n").unwrap();
+        assert_eq!(cache.lookup(&image.url).await, None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn report_page_meta() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+
+        let outcome = fetcher
+            .fetch(&FetchSearch {
+                keyword: String::from("woodpecker"),
+            })
+            .swarm(Some(Swarm::Auto {
+                page_size: 11,
+                max_pages: None,
+                workers: None,
+            }))
+            .execute_with_report()
+            .await
+            .unwrap();
+        assert_eq!(outcome.resource.len(), 33);
+        assert_eq!(outcome.reports.len(), 3);
+        assert!(outcome.reports.iter().all(|r| r.meta.count == Some(33)));
+        assert_eq!(server.requests().len(), 3);
+
+        let outcome = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Auto {
+                page_size: 30,
+                max_pages: Some(3),
+                workers: None,
+            }))
+            .execute_with_report()
+            .await
+            .unwrap();
+        assert_eq!(outcome.resource.len(), 90);
+        assert_eq!(outcome.reports.len(), 3);
+        let meta = outcome.reports[0].meta;
+        assert_eq!(meta.code, Some(0));
+        assert_eq!(meta.count, None);
+        assert_eq!(
+            meta.timestamp,
+            Utc.timestamp_opt(FIXTURE_TIMESTAMP, 0).single()
+        );
+
+        let mut fetcher: Fetcher<API, ReplySet> = server.fetcher();
+        let outcome = fetcher
+            .fetch(&FetchReply {
+                hole_id: HoleID(1075),
+            })
+            .execute_with_report()
+            .await
+            .unwrap();
+        assert_eq!(outcome.reports[0].meta.attention, Some(true));
+    }
+
+    #[tokio::test]
+    async fn perform_actions() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+
+        let id = fetcher
+            .perform(&PostHole {
+                text: String::from("A new hole."),
+                image: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(id, HoleID(1101));
+        let holes = fetcher.fetch(&FetchSingle { id }).execute().await.unwrap();
+        assert_eq!(holes.iter().next().unwrap().entry.text, "A new hole.");
+
+        let id = fetcher
+            .perform(&PostHole {
+                text: String::from("A new image."),
+                image: Some(Upload {
+                    filename: String::from("woodpecker.png"),
+                    bytes: vec![0x89, b'P', b'N', b'G', b'\r', b'\n'],
+                }),
+            })
+            .await
+            .unwrap();
+        let holes = fetcher.fetch(&FetchSingle { id }).execute().await.unwrap();
+        let hole = &holes.iter().next().unwrap().entry;
+        assert_eq!(hole.text, "A new image.");
+        assert_eq!(
+            hole.kind,
+            HoleKind::Image {
+                url: String::from("woodpecker.png")
+            }
+        );
+
+        let result = fetcher
+            .perform(&PostHole {
+                text: String::new(),
+                image: None,
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(FetcherError::ParseResourceFail(
+                ParseResourceError::Backend { code: -1, .. }
+            ))
+        ));
+
+        let reply = fetcher
+            .perform(&PostReply {
+                hole_id: HoleID(1001),
+                text: String::from("A new reply."),
+            })
+            .await
+            .unwrap();
+        let mut replies: Fetcher<API, ReplySet> = server.fetcher();
+        let thread = replies
+            .fetch(&FetchReply {
+                hole_id: HoleID(1001),
+            })
+            .execute()
+            .await
+            .unwrap();
+        let last = thread.iter().next_back().unwrap();
+        assert_eq!(last.entry.id, reply);
+        assert_eq!(last.entry.text, "A new reply.");
+
+        fetcher
+            .perform(&SetAttention {
+                hole_id: HoleID(1001),
+                follow: true,
+            })
+            .await
+            .unwrap();
+        assert!(server.fixture().hole(1001).unwrap().attention);
+        fetcher
+            .perform(&SetAttention {
+                hole_id: HoleID(1001),
+                follow: false,
+            })
+            .await
+            .unwrap();
+        assert!(!server.fixture().hole(1001).unwrap().attention);
+    }
+
+    #[tokio::test]
+    async fn manage_credentials() {
+        let server = MockServer::start(Fixture::default()).await;
+
+        let fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        fetcher.validate_token().await.unwrap();
+        let fetcher: Fetcher<API, ReplySet> =
+            Fetcher::new(API::new(&server.base(), None, "expired-token"));
+        match fetcher.validate_token().await.unwrap_err() {
+            CredentialError::TokenExpired { msg } => assert_eq!(msg.as_deref(), Some("请先登录")),
+            e => panic!("unexpected error {:?}", e),
+        }
+
+        let path = std::env::temp_dir().join(format!("woodpecker-token-{}", std::process::id()));
+        std::fs::write(&path, format!("  {}\n", MOCK_USER_TOKEN)).unwrap();
+        assert_eq!(
+            FileToken { path: path.clone() }.token().unwrap(),
+            MOCK_USER_TOKEN
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            FileToken { path }.token(),
+            Err(CredentialError::Io { .. })
+        ));
+        let missing = EnvToken {
+            var: String::from("WOODPECKER_MISSING_TOKEN"),
+        };
+        assert!(matches!(missing.token(), Err(CredentialError::NotFound(_))));
+        let chain = ProviderChain::default()
+            .or(missing)
+            .or(|| Ok(String::from(MOCK_USER_TOKEN)));
+        assert_eq!(chain.token().unwrap(), MOCK_USER_TOKEN);
+
+        let pool = Arc::new(TokenPool::new(["expired-token", MOCK_USER_TOKEN]));
+        let mut fetcher: Fetcher<API, HoleSet> =
+            Fetcher::new(API::with_token_pool(&server.base(), None, Arc::clone(&pool)).unwrap());
+        fetcher.validate_token_for(MOCK_USER_TOKEN).await.unwrap();
+        assert!(matches!(
+            fetcher.validate_token_for("expired-token").await,
+            Err(CredentialError::TokenExpired { .. })
+        ));
+        assert_eq!(pool.active(), ["expired-token", MOCK_USER_TOKEN]);
+        let fetch = FetchSingle { id: HoleID(1025) };
+        let requests = server.requests().len();
+        assert_eq!(fetcher.fetch(&fetch).execute().await.unwrap().len(), 1);
+        assert_eq!(server.requests().len(), requests + 2);
+        assert_eq!(pool.active(), [MOCK_USER_TOKEN]);
+        for _ in 0..2 {
+            assert_eq!(fetcher.fetch(&fetch).execute().await.unwrap().len(), 1);
+        }
+
+        pool.expire(MOCK_USER_TOKEN);
+        let requests = server.requests().len();
+        let error = fetcher.fetch(&fetch).execute().await.unwrap_err();
+        assert!(matches!(
+            error,
+            FetcherError::CredentialFail(CredentialError::TokenExpired { .. })
+        ));
+        assert_eq!(server.requests().len(), requests);
+        let error = API::with_token_pool(&server.base(), None, pool)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            FetcherError::CredentialFail(CredentialError::TokenExpired { .. })
+        ));
+
+        let pool = Arc::new(TokenPool::new(["expired-a", "expired-b"]));
+        let mut fetcher: Fetcher<API, HoleSet> =
+            Fetcher::new(API::with_token_pool(&server.base(), None, Arc::clone(&pool)).unwrap());
+        let error = fetcher.fetch(&fetch).execute().await.unwrap_err();
+        assert!(error.is_token_expired());
+        assert!(pool.active().is_empty());
+
+        let pool = Arc::new(TokenPool::new(["token-a", "token-b"]));
+        let api = API::with_token_pool(&server.base(), None, pool).unwrap();
+        assert_eq!(api.next_token().unwrap(), "token-a");
+        assert_eq!(api.next_token().unwrap(), "token-b");
+        assert_eq!(api.user_token(), "token-a");
+    }
+
+    #[test]
+    fn construct_api_without_panics() {
+        let error = API::try_new("not a url", None, MOCK_USER_TOKEN)
+            .err()
+            .unwrap();
+        assert!(matches!(error, FetcherError::MalformedUrl(_)));
+        let pool = Arc::new(TokenPool::new([MOCK_USER_TOKEN]));
+        let error = API::with_token_pool("/relative", None, pool).err().unwrap();
+        assert!(matches!(error, FetcherError::MalformedUrl(_)));
+        assert!(matches!(
+            TokenPool::try_new(Vec::<String>::new()),
+            Err(CredentialError::NotFound(_))
+        ));
+        let error = API::from_provider(&EnvToken {
+            var: String::from("WOODPECKER_MISSING_TOKEN"),
+        })
+        .err()
+        .unwrap();
+        assert!(matches!(
+            error,
+            FetcherError::CredentialFail(CredentialError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reuse_clients() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+        assert_eq!(fetcher.client_count(), 0);
+        fetcher
+            .fetch(&FetchSingle { id: HoleID(1001) })
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(fetcher.client_count(), 1);
+        let fetch = FetchEach(vec![HoleID(1001), HoleID(1002), HoleID(1003)]);
+        for workers in [3, 2] {
+            let result = fetcher
+                .fetch(&fetch)
+                .swarm(Some(Swarm::Concurrent {
+                    count: 3,
+                    page_size: 1,
+                    workers: Some(workers),
+                }))
+                .execute()
+                .await
+                .unwrap();
+            assert_eq!(result.len(), 3);
+            assert_eq!(fetcher.client_count(), 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn build_fetcher() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, HoleSet> = Fetcher::builder(server.api())
+            .header(
+                HeaderName::from_static("x-woodpecker"),
+                HeaderValue::from_static("test"),
+            )
+            .user_agents([
+                HeaderValue::from_static("agent-a"),
+                HeaderValue::from_static("agent-b"),
+            ])
+            .timeout(Duration::from_secs(5))
+            .max_workers(2)
+            .build()
+            .unwrap();
+        assert_eq!(fetcher.client_count(), 1);
+        fetcher
+            .fetch(&FetchSingle { id: HoleID(1001) })
+            .execute()
+            .await
+            .unwrap();
+        let fetch = FetchEach(vec![HoleID(1001), HoleID(1002), HoleID(1003)]);
+        let result = fetcher
+            .fetch(&fetch)
+            .swarm(Some(Swarm::Concurrent {
+                count: 3,
+                page_size: 1,
+                workers: None,
+            }))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(fetcher.client_count(), 2);
+
+        let headers = server.request_headers();
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers[0][USER_AGENT], "agent-a");
+        for headers in &headers {
+            assert_eq!(headers["x-woodpecker"], "test");
+            assert!(["agent-a", "agent-b"].contains(&headers[USER_AGENT].to_str().unwrap()));
+            assert!(headers.contains_key(REFERER));
+        }
+
+        let mut fetcher: Fetcher<API, HoleSet> = Fetcher::builder(server.api())
+            .proxy(Proxy::all("http://127.0.0.1:9").unwrap())
+            .connect_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+        let error = fetcher
+            .fetch(&FetchSingle { id: HoleID(1001) })
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, FetcherError::RequestFail(_)));
+        assert_eq!(server.request_headers().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn share_concurrency_limit() {
+        let server = MockServer::start(Fixture::default()).await;
+        server.set_latency(Duration::from_millis(50));
+        let limit = ConcurrencyLimit::fixed(2);
+        let mut holes: Fetcher<API, HoleSet> =
+            server.fetcher().with_concurrency_limit(limit.clone());
+        let mut others: Fetcher<API, HoleSet> =
+            server.fetcher().with_concurrency_limit(limit.clone());
+        let fetch = FetchEach(vec![HoleID(1001), HoleID(1002), HoleID(1003)]);
+        let swarm = Swarm::Concurrent {
+            count: 3,
+            page_size: 1,
+            workers: Some(3),
+        };
+        let mut first = holes.fetch(&fetch).swarm(Some(swarm));
+        let mut second = others.fetch(&fetch).swarm(Some(swarm));
+        let (first, second) = tokio::join!(first.execute(), second.execute());
+        assert_eq!(first.unwrap().len(), 3);
+        assert_eq!(second.unwrap().len(), 3);
+        assert_eq!(server.peak_in_flight(), 2);
+        assert_eq!(limit.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn adapt_concurrency() {
+        let server = MockServer::start(Fixture::default()).await;
+        let limit = ConcurrencyLimit::adaptive(1, 4);
+        let mut fetcher: Fetcher<API, HoleSet> =
+            server.fetcher().with_concurrency_limit(limit.clone());
+        let fetch = FetchSingle { id: HoleID(1001) };
+
+        server.fail_next(1, StatusCode::TOO_MANY_REQUESTS);
+        fetcher.fetch(&fetch).execute().await.unwrap_err();
+        assert_eq!(limit.limit(), 2);
+        server.fail_next(1, StatusCode::BAD_GATEWAY);
+        fetcher.fetch(&fetch).execute().await.unwrap_err();
+        assert_eq!(limit.limit(), 1);
+        server.fail_next(1, StatusCode::BAD_GATEWAY);
+        fetcher.fetch(&fetch).execute().await.unwrap_err();
+        assert_eq!(limit.limit(), 1);
+
+        fetcher.fetch(&fetch).execute().await.unwrap();
+        assert_eq!(limit.limit(), 2);
+        for _ in 0..2 {
+            fetcher.fetch(&fetch).execute().await.unwrap();
+        }
+        assert_eq!(limit.limit(), 3);
+    }
+
+    #[tokio::test]
+    async fn build_conversation() {
+        let server = MockServer::start(Fixture::default()).await;
+        let mut fetcher: Fetcher<API, ReplySet> = server.fetcher();
+        let replies = fetcher
+            .fetch(&FetchReply {
+                hole_id: HoleID(1003),
+            })
+            .execute()
+            .await
+            .unwrap();
+        let mut texts = replies
+            .iter()
+            .map(|r| (r.entry.reply_to.as_deref(), &*r.entry.text));
+        assert_eq!(texts.next(), Some((None, "Reply 0 to hole 1003.")));
+        assert_eq!(texts.next(), Some((Some("洞主"), "Reply 1 to hole 1003.")));
+
+        let conversation = Conversation::from_entries(&replies);
+        let walked = conversation
+            .walk()
+            .into_iter()
+            .map(|(depth, reply)| (depth, reply.id.0))
+            .collect::<Vec<_>>();
+        assert_eq!(walked, [(0, 100300), (1, 100301), (1, 100302)]);
     }
 }
@@ -0,0 +1,124 @@
+use serde::{de::Error as _, Deserialize};
+use serde_json::Value;
+
+use super::*;
+use crate::{
+    common::{Action, ActionBody, ActionPart},
+    hole::{reply::ReplyID, HoleID},
+};
+
+fn with_action(mut url: Url, action: &str) -> Url {
+    url.query_pairs_mut().append_pair("action", action);
+    url
+}
+
+/// Reads an id the backend returns either as a number or as a string.
+fn read_id(data: Value) -> Result<usize, serde_json::Error> {
+    match data {
+        Value::String(id) => id.parse().map_err(serde_json::Error::custom),
+        data => usize::deserialize(data),
+    }
+}
+
+/// A file uploaded along with an action.
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub struct Upload {
+    pub filename: String,
+    pub bytes: Vec<u8>,
+}
+
+/// The action of posting a hole, returning its id.
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub struct PostHole {
+    pub text: String,
+    /// The image of an image hole, uploaded as multipart.
+    pub image: Option<Upload>,
+}
+
+impl Action for PostHole {
+    type Output = HoleID;
+
+    fn locate(&self, url: Url) -> Url {
+        with_action(url, "dopost")
+    }
+
+    fn body(&self) -> ActionBody {
+        match &self.image {
+            None => ActionBody::Form(vec![
+                (String::from("text"), self.text.clone()),
+                (String::from("type"), String::from("text")),
+            ]),
+            Some(Upload { filename, bytes }) => ActionBody::Multipart(vec![
+                (String::from("text"), ActionPart::Text(self.text.clone())),
+                (
+                    String::from("type"),
+                    ActionPart::Text(String::from("image")),
+                ),
+                (
+                    String::from("data"),
+                    ActionPart::File {
+                        filename: filename.clone(),
+                        mime: None,
+                        bytes: bytes.clone(),
+                    },
+                ),
+            ]),
+        }
+    }
+
+    fn output(data: Value) -> Result<HoleID, serde_json::Error> {
+        read_id(data).map(HoleID::from)
+    }
+}
+
+/// The action of replying to a hole, returning the id of the reply.
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub struct PostReply {
+    pub hole_id: HoleID,
+    pub text: String,
+}
+
+impl Action for PostReply {
+    type Output = ReplyID;
+
+    fn locate(&self, url: Url) -> Url {
+        with_action(url, "docomment")
+    }
+
+    fn body(&self) -> ActionBody {
+        ActionBody::Form(vec![
+            (String::from("pid"), String::from(self.hole_id)),
+            (String::from("text"), self.text.clone()),
+        ])
+    }
+
+    fn output(data: Value) -> Result<ReplyID, serde_json::Error> {
+        read_id(data).map(ReplyID::from)
+    }
+}
+
+/// The action of following or unfollowing a hole.
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub struct SetAttention {
+    pub hole_id: HoleID,
+    pub follow: bool,
+}
+
+impl Action for SetAttention {
+    type Output = ();
+
+    fn locate(&self, url: Url) -> Url {
+        with_action(url, "attention")
+    }
+
+    fn body(&self) -> ActionBody {
+        ActionBody::Form(vec![
+            (String::from("pid"), String::from(self.hole_id)),
+            (String::from("switch"), usize::from(self.follow).to_string()),
+        ])
+    }
+
+    fn output(_data: Value) -> Result<(), serde_json::Error> {
+        Ok(())
+    }
+}
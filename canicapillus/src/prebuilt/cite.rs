@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use futures::StreamExt;
+
+use super::{crawl::DEFAULT_CONCURRENCY, *};
+use crate::{
+    common::Endpoint,
+    fetcher::{Fetcher, FetcherError},
+    hole::HoleID,
+    HoleEntry,
+};
+
+/// The default number of citations followed from a hole, transitively.
+const DEFAULT_MAX_DEPTH: usize = 2;
+/// The default number of holes requested by a crawl, including the first one.
+const DEFAULT_MAX_HOLES: usize = 32;
+
+/// The action of fetching a hole and the holes it cites as `#1234567`, recursively.
+///
+/// Holes are requested level by level, as [`FetchSingle`] would, and their replies by
+/// [`FetchReplies`] if a replies fetcher is given. Citations in replies are followed too.
+/// Every hole is requested at most once, so cycles of citations end the crawl.
+///
+/// ```ignore
+/// let graph = FetchCitations::new(HoleID(3558999))
+///     .max_depth(1)
+///     .crawl(&mut holes_fetcher, Some(&mut replies_fetcher))
+///     .await;
+/// for (citing, cited) in &graph.edges {
+///     dbg!(citing, cited);
+/// }
+/// ```
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub struct FetchCitations {
+    root: HoleID,
+    max_depth: usize,
+    max_holes: usize,
+    concurrency: usize,
+}
+
+/// A hole reached by a [`FetchCitations`].
+#[derive(Debug)]
+pub struct CitedHole {
+    pub entry: HoleEntry,
+    /// The number of citations followed from the first hole, 0 for the first hole itself.
+    pub depth: usize,
+    /// The replies of the hole, `None` unless requested and arrived.
+    pub replies: Option<ReplySet>,
+}
+
+/// Holes and their citations, as crawled by a [`FetchCitations`].
+#[derive(Debug, Default)]
+pub struct CitationGraph {
+    pub nodes: BTreeMap<HoleID, CitedHole>,
+    /// Citations as `(citing, cited)`, including those of holes not in `nodes`.
+    pub edges: BTreeSet<(HoleID, HoleID)>,
+    /// Holes failing to arrive.
+    pub failures: BTreeMap<HoleID, FetcherError>,
+    /// Holes whose pages arrive without the hole, e.g. once deleted.
+    pub missing: BTreeSet<HoleID>,
+    /// Holes whose replies fail to arrive, themselves in `nodes`.
+    pub reply_failures: BTreeMap<HoleID, FetcherError>,
+    /// Cited holes left out by the depth or count limit.
+    pub truncated: BTreeSet<HoleID>,
+}
+
+impl CitationGraph {
+    /// Holes cited by the hole `id`.
+    pub fn cites(&self, id: HoleID) -> impl Iterator<Item = HoleID> + '_ {
+        self.edges
+            .range((id, HoleID(0))..=(id, HoleID(usize::MAX)))
+            .map(|(_, cited)| *cited)
+    }
+
+    /// Holes citing the hole `id`.
+    pub fn cited_by(&self, id: HoleID) -> impl Iterator<Item = HoleID> + '_ {
+        self.edges
+            .iter()
+            .filter(move |(_, cited)| *cited == id)
+            .map(|(citing, _)| *citing)
+    }
+
+    /// Whether some holes cite each other in a circle, a hole citing itself included.
+    pub fn has_cycle(&self) -> bool {
+        // `false` while a hole is being visited, `true` once all its citations are
+        let mut visited = BTreeMap::<HoleID, bool>::new();
+        self.nodes
+            .keys()
+            .any(|id| self.visit_cycle(*id, &mut visited))
+    }
+
+    fn visit_cycle(&self, id: HoleID, visited: &mut BTreeMap<HoleID, bool>) -> bool {
+        match visited.get(&id) {
+            Some(done) => return !done,
+            None => visited.insert(id, false),
+        };
+        if self.cites(id).any(|cited| self.visit_cycle(cited, visited)) {
+            return true;
+        }
+        visited.insert(id, true);
+        false
+    }
+
+    /// Whether every hole and reply requested arrives.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty() && self.missing.is_empty() && self.reply_failures.is_empty()
+    }
+}
+
+impl FetchCitations {
+    /// Starts crawling from the hole `root`.
+    pub fn new(root: HoleID) -> Self {
+        FetchCitations {
+            root,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_holes: DEFAULT_MAX_HOLES,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Follows citations at most `max_depth` times from the first hole. With 0, only the
+    /// first hole is requested.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Requests at most `max_holes` holes, the first one included.
+    pub fn max_holes(mut self, max_holes: usize) -> Self {
+        self.max_holes = max_holes.max(1);
+        self
+    }
+
+    /// Requests at most `concurrency` holes, or replies of holes, at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn root(&self) -> HoleID {
+        self.root
+    }
+
+    /// Crawls the citations breadth first, nearer holes before farther ones.
+    ///
+    /// Failing holes are reported in [`CitationGraph::failures`], and holes arriving empty
+    /// in [`CitationGraph::missing`]. Neither stops the others.
+    pub async fn crawl<A, B>(
+        &self,
+        holes: &mut Fetcher<A, HoleSet>,
+        mut replies: Option<&mut Fetcher<B, ReplySet>>,
+    ) -> CitationGraph
+    where
+        A: Endpoint<HoleSet>,
+        B: Endpoint<ReplySet>,
+    {
+        let mut graph = CitationGraph::default();
+        let mut seen = BTreeSet::from([self.root]);
+        let mut frontier = vec![self.root];
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            let level = FetchLevel {
+                ids: frontier,
+                concurrency: self.concurrency,
+            };
+            let mut pages = holes.fetch(&level).stream();
+            let mut arrived = Vec::new();
+            while let Some(page) = pages.next().await {
+                let id = level.ids[page.page - 1];
+                match page.result.map(|set| set.into_iter().next()) {
+                    Ok(Some(entry)) => {
+                        arrived.push(id);
+                        graph.nodes.insert(
+                            id,
+                            CitedHole {
+                                entry,
+                                depth,
+                                replies: None,
+                            },
+                        );
+                    }
+                    Ok(None) => {
+                        graph.missing.insert(id);
+                    }
+                    Err(e) => {
+                        graph.failures.insert(id, e);
+                    }
+                }
+            }
+            drop(pages);
+            arrived.sort();
+
+            if let Some(replies) = replies.as_deref_mut() {
+                let crawled = FetchReplies::new(arrived.iter().copied())
+                    .concurrency(self.concurrency)
+                    .crawl(replies)
+                    .await;
+                for (id, replies) in crawled.replies {
+                    graph.nodes.get_mut(&id).unwrap().replies = Some(replies);
+                }
+                graph.reply_failures.extend(crawled.failures);
+            }
+
+            frontier = Vec::new();
+            for id in arrived {
+                let node = &graph.nodes[&id];
+                let mut cited = node.entry.entry.citations();
+                for reply in node.replies.iter().flatten() {
+                    cited.extend(reply.entry.citations());
+                }
+                for cited in cited {
+                    graph.edges.insert((id, cited));
+                    if !seen.insert(cited) {
+                        continue;
+                    }
+                    if depth < self.max_depth
+                        && seen.len() - graph.truncated.len() <= self.max_holes
+                    {
+                        frontier.push(cited);
+                    } else {
+                        graph.truncated.insert(cited);
+                    }
+                }
+            }
+            depth += 1;
+        }
+        graph
+    }
+}
+
+/// Fetches the holes of a level of a [`FetchCitations`], one hole per page.
+struct FetchLevel {
+    ids: Vec<HoleID>,
+    concurrency: usize,
+}
+
+impl Location<HoleSet> for FetchLevel {
+    fn locate(&self, url: Url) -> Url {
+        url
+    }
+
+    fn dispatch(
+        &self,
+        url: Url,
+        _swarm: Option<&Swarm>,
+        page: usize,
+        _page_size: usize,
+    ) -> Result<Url, SwarmError> {
+        let id = self.ids.get(page - 1).ok_or(SwarmError::Unsupported)?;
+        Ok(FetchSingle { id: *id }.locate(url))
+    }
+
+    fn default_swarm(&self) -> Option<Swarm> {
+        Some(Swarm::Concurrent {
+            count: self.ids.len(),
+            page_size: 1,
+            workers: Some(self.concurrency),
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use futures::StreamExt;
+
+use super::*;
+use crate::{
+    common::Endpoint,
+    fetcher::{Fetcher, FetcherError},
+    hole::HoleID,
+};
+
+/// The default number of holes, or replies of holes, requested at once.
+pub(super) const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The action of fetching the replies of many holes, one hole per page.
+///
+/// ```ignore
+/// let holes = holes_fetcher.fetch(&FetchFeed).execute().await?;
+/// let crawled = FetchReplies::from_holes(&holes).crawl(&mut replies_fetcher).await;
+/// for (id, replies) in &crawled.replies {
+///     dbg!(id, replies.len());
+/// }
+/// ```
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub struct FetchReplies {
+    ids: Vec<HoleID>,
+    concurrency: usize,
+}
+
+/// The replies of many holes, and the holes whose replies fail.
+#[derive(Debug, Default)]
+pub struct CrawledReplies {
+    pub replies: BTreeMap<HoleID, ReplySet>,
+    pub failures: BTreeMap<HoleID, FetcherError>,
+}
+
+impl CrawledReplies {
+    /// Whether the replies of every hole arrive.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl FetchReplies {
+    /// Fetches the replies of every hole in `ids`, skipping duplicates.
+    pub fn new(ids: impl IntoIterator<Item = HoleID>) -> Self {
+        let mut seen = BTreeSet::new();
+        FetchReplies {
+            ids: ids.into_iter().filter(|id| seen.insert(*id)).collect(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Fetches the replies of every hole in `holes`.
+    pub fn from_holes(holes: &HoleSet) -> Self {
+        Self::new(holes.iter().map(|hole| hole.entry.id))
+    }
+
+    /// Requests the replies of at most `concurrency` holes at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn ids(&self) -> &[HoleID] {
+        &self.ids
+    }
+
+    /// Fetches the replies of every hole on a pool of clients, keeping them apart by hole.
+    ///
+    /// Failing holes are reported in [`CrawledReplies::failures`] and do not stop the others.
+    pub async fn crawl<A>(&self, fetcher: &mut Fetcher<A, ReplySet>) -> CrawledReplies
+    where
+        A: Endpoint<ReplySet>,
+    {
+        let mut crawled = CrawledReplies::default();
+        let mut pages = fetcher.fetch(self).stream();
+        while let Some(page) = pages.next().await {
+            let id = self.ids[page.page - 1];
+            match page.result {
+                Ok(replies) => {
+                    crawled.replies.insert(id, replies);
+                }
+                Err(e) => {
+                    crawled.failures.insert(id, e);
+                }
+            }
+        }
+        crawled
+    }
+}
+
+impl Location<ReplySet> for FetchReplies {
+    fn locate(&self, mut url: Url) -> Url {
+        url.query_pairs_mut().append_pair("action", "getcomment");
+        url
+    }
+
+    fn dispatch(
+        &self,
+        mut url: Url,
+        _swarm: Option<&Swarm>,
+        page: usize,
+        _page_size: usize,
+    ) -> Result<Url, SwarmError> {
+        let id = self.ids.get(page - 1).ok_or(SwarmError::Unsupported)?;
+        url.query_pairs_mut().append_pair("pid", &String::from(*id));
+        Ok(url)
+    }
+
+    /// Fetches the replies of every hole concurrently, `concurrency` holes at once.
+    fn default_swarm(&self) -> Option<Swarm> {
+        Some(Swarm::Concurrent {
+            count: self.ids.len(),
+            page_size: 1,
+            workers: Some(self.concurrency),
+        })
+    }
+}
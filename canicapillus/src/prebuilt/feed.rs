@@ -38,6 +38,7 @@ impl Location<HoleSet> for FetchFeed {
         Some(Swarm::Concurrent {
             count: 4,
             page_size: 30,
+            workers: None,
         })
     }
 }
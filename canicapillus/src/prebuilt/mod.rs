@@ -7,10 +7,26 @@ use crate::{
     ReplySet,
 };
 
+mod action;
 mod attention;
+mod cite;
+mod crawl;
 mod feed;
 mod reply;
 mod search;
 mod single;
+mod sync;
+mod thread;
 
-pub use {attention::FetchAttention, feed::FetchFeed, reply::FetchReply, search::FetchSearch, single::FetchSingle};
+pub use {
+    action::{PostHole, PostReply, SetAttention, Upload},
+    attention::FetchAttention,
+    cite::{CitationGraph, CitedHole, FetchCitations},
+    crawl::{CrawledReplies, FetchReplies},
+    feed::FetchFeed,
+    reply::FetchReply,
+    search::FetchSearch,
+    single::FetchSingle,
+    sync::{Counters, FeedDelta, FeedSync},
+    thread::{FetchThread, Thread},
+};
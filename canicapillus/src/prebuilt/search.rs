@@ -50,6 +50,7 @@ impl Location<HoleSet> for FetchSearch {
         Some(Swarm::Concurrent {
             count: 3,
             page_size: 50,
+            workers: None,
         })
     }
 }
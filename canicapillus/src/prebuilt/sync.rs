@@ -0,0 +1,159 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use super::*;
+use crate::{
+    common::Endpoint,
+    fetcher::{Fetcher, FetcherError, Strictness},
+    hole::HoleID,
+};
+
+/// The number of holes on a page of live feed.
+const FEED_PAGE_SIZE: usize = 30;
+
+/// The counters of a hole which change after it is posted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Counters {
+    pub reply: usize,
+    pub likenum: usize,
+}
+
+impl From<&hole::Hole> for Counters {
+    fn from(hole: &hole::Hole) -> Self {
+        Counters {
+            reply: hole.reply,
+            likenum: hole.likenum,
+        }
+    }
+}
+
+/// The changes of live feed since the last sync.
+#[derive(Debug, Default)]
+pub struct FeedDelta {
+    /// Holes newer than every known hole.
+    pub fresh: HoleSet,
+    /// Known holes seen again whose counters change, or every known hole seen again
+    /// if its counters are unknown.
+    pub updated: BTreeMap<HoleID, Counters>,
+    /// Holes left unfetched when `max_pages` runs out before reaching a known hole.
+    ///
+    /// The sync does not advance past a gap, so the next sync fetches `fresh` again.
+    pub gap: Option<Range<HoleID>>,
+}
+
+/// Incremental sync of live feed.
+///
+/// Pages through [`FetchFeed`] until a page reaches an already seen hole, instead of
+/// refetching a fixed number of pages. Every sync advances the state, so the same
+/// [`FeedSync`] can be polled repeatedly. Only the counters of holes on the pages of the
+/// last sync are remembered, so the state stays small however long it is polled.
+///
+/// ```ignore
+/// let mut sync = FeedSync::from_known(&holes);
+/// loop {
+///     let delta = sync.sync(&mut fetcher).await?;
+///     dbg!(delta.fresh.len(), delta.updated.len());
+///     tokio::time::sleep(Duration::from_secs(60)).await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FeedSync {
+    since: HoleID,
+    counters: BTreeMap<HoleID, Counters>,
+    max_pages: Option<usize>,
+}
+
+impl FeedSync {
+    /// Syncs holes newer than `since`.
+    pub fn since(since: HoleID) -> Self {
+        FeedSync {
+            since,
+            counters: BTreeMap::new(),
+            max_pages: None,
+        }
+    }
+
+    /// Syncs holes newer than every hole of `known`, remembering their counters.
+    pub fn from_known(known: &HoleSet) -> Self {
+        FeedSync {
+            since: known.iter().next_back().map_or(HoleID(0), |h| h.entry.id),
+            counters: known
+                .iter()
+                .map(|h| (h.entry.id, Counters::from(&h.entry)))
+                .collect(),
+            max_pages: None,
+        }
+    }
+
+    /// Fetches at most `max_pages` pages per sync, unlimited by default.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// The newest hole seen so far.
+    pub fn latest(&self) -> HoleID {
+        self.since
+    }
+
+    /// Fetches the changes of live feed since the last sync.
+    ///
+    /// Fails on the first failing page, leaving the state untouched, since a missing page
+    /// would silently lose holes. For the same reason, the state does not advance if
+    /// `max_pages` stops the sync short of a known hole, see [`FeedDelta::gap`].
+    pub async fn sync<A>(
+        &mut self,
+        fetcher: &mut Fetcher<A, HoleSet>,
+    ) -> Result<FeedDelta, FetcherError>
+    where
+        A: Endpoint<HoleSet>,
+    {
+        let since = self.since;
+        let outcome = fetcher
+            .fetch(&FetchFeed)
+            .swarm(Some(Swarm::Exhaustive {
+                page_size: FEED_PAGE_SIZE,
+                max_pages: self.max_pages,
+            }))
+            .strictness(Strictness::FailFast)
+            // holes are ordered by id, so the first one is the oldest on the page
+            .until(move |holes: &HoleSet| holes.iter().next().is_some_and(|h| h.entry.id <= since))
+            .execute_with_report()
+            .await?;
+
+        // a short last page is the end of the feed, while a full one is cut by `max_pages`
+        let truncated = outcome.reports.last().is_some_and(|report| {
+            report
+                .result
+                .as_ref()
+                .is_ok_and(|size| *size >= FEED_PAGE_SIZE)
+        });
+        let oldest = outcome.resource.iter().next().map(|h| h.entry.id);
+        let mut delta = FeedDelta {
+            gap: oldest
+                .filter(|oldest| truncated && oldest.0 > since.0 + 1)
+                .map(|oldest| HoleID(since.0 + 1)..oldest),
+            ..FeedDelta::default()
+        };
+        for hole in outcome.resource {
+            let id = hole.entry.id;
+            let counters = Counters::from(&hole.entry);
+            if id > since {
+                self.counters.insert(id, counters);
+                delta.fresh.insert(hole);
+            } else if self.counters.insert(id, counters) != Some(counters) {
+                delta.updated.insert(id, counters);
+            }
+        }
+        if delta.gap.is_some() {
+            return Ok(delta);
+        }
+        if let Some(latest) = delta.fresh.iter().next_back() {
+            self.since = latest.entry.id;
+        }
+        // older holes are not seen again unless they show up on the pages of a later sync
+        if let Some(oldest) = oldest {
+            self.counters = self.counters.split_off(&oldest);
+        }
+        Ok(delta)
+    }
+}
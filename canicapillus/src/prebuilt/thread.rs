@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use reqwest::Response;
+
+use super::*;
+use crate::{
+    common::{
+        read_page, MergeResource, MergeResourceError, MergeStrategy, PageMeta, ParseResource,
+        ParseResourceError, Resource,
+    },
+    hole::{reply::RawReplyPage, RawHolePage},
+    HoleEntry, ReplyEntry,
+};
+
+/// A hole together with its replies.
+#[derive(Debug, Default)]
+pub struct Thread {
+    /// The hole, `None` until its page arrives.
+    pub hole: Option<HoleEntry>,
+    /// Replies ordered by id.
+    pub replies: ReplySet,
+    /// Whether the user follows the hole, `None` until the replies arrive.
+    pub attention: Option<bool>,
+}
+
+impl Resource for Thread {
+    fn size(&self) -> usize {
+        usize::from(self.hole.is_some()) + self.replies.len()
+    }
+}
+
+#[async_trait]
+impl ParseResource<Thread> for Thread {
+    async fn parse(response: Response) -> Result<Thread, ParseResourceError> {
+        Ok(Self::parse_with_meta(response).await?.0)
+    }
+
+    /// Parses the hole of a thread, as fetched without a swarm.
+    async fn parse_with_meta(response: Response) -> Result<(Thread, PageMeta), ParseResourceError> {
+        Self::parse_page(response, 1).await
+    }
+
+    /// Parses either half of a thread, the hole on page 1 and its replies on page 2,
+    /// as dispatched by [`FetchThread`].
+    async fn parse_page(
+        response: Response,
+        page: usize,
+    ) -> Result<(Thread, PageMeta), ParseResourceError> {
+        if page == 1 {
+            let page: RawHolePage = read_page(response).await?;
+            let meta = PageMeta::from(&page);
+            let thread = Thread {
+                hole: page.into_iter().next(),
+                ..Thread::default()
+            };
+            Ok((thread, meta))
+        } else {
+            let page: RawReplyPage = read_page(response).await?;
+            let meta = PageMeta::from(&page);
+            let thread = Thread {
+                replies: page.into_iter().collect(),
+                attention: meta.attention,
+                ..Thread::default()
+            };
+            Ok((thread, meta))
+        }
+    }
+}
+
+impl MergeResource<Thread> for Thread {
+    type Entry = ReplyEntry;
+
+    fn merge(lhs: Thread, rhs: Thread) -> Result<Thread, MergeResourceError> {
+        Self::merge_with(lhs, rhs, &MergeStrategy::FirstWins)
+    }
+
+    fn merge_with(
+        lhs: Thread,
+        rhs: Thread,
+        strategy: &MergeStrategy<ReplyEntry>,
+    ) -> Result<Thread, MergeResourceError> {
+        Ok(Thread {
+            hole: match (lhs.hole, rhs.hole) {
+                (Some(former), Some(latter)) => {
+                    Some(MergeStrategy::NewestSnapshot.resolve(former, latter))
+                }
+                (former, latter) => former.or(latter),
+            },
+            replies: strategy.merge(lhs.replies, rhs.replies),
+            attention: rhs.attention.or(lhs.attention),
+        })
+    }
+}
+
+/// The action of fetching a hole and its replies at once.
+///
+/// The hole and its replies are two pages of a concurrent swarm, requested together.
+/// Without a swarm, only the hole is fetched.
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub struct FetchThread {
+    pub id: hole::HoleID,
+}
+
+impl Location<Thread> for FetchThread {
+    fn locate(&self, mut url: Url) -> Url {
+        url.query_pairs_mut()
+            .append_pair("pid", &String::from(self.id));
+        url
+    }
+
+    /// Dispatches the hole on page 1 and its replies on page 2.
+    fn dispatch(
+        &self,
+        mut url: Url,
+        _swarm: Option<&Swarm>,
+        page: usize,
+        _page_size: usize,
+    ) -> Result<Url, SwarmError> {
+        let action = match page {
+            1 => "getone",
+            2 => "getcomment",
+            _ => return Err(SwarmError::Unsupported),
+        };
+        url.query_pairs_mut().append_pair("action", action);
+        Ok(url)
+    }
+
+    fn default_swarm(&self) -> Option<Swarm> {
+        Some(Swarm::Concurrent {
+            count: 2,
+            page_size: 1,
+            workers: None,
+        })
+    }
+}
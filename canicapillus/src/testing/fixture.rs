@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+/// The user token accepted by a [`super::MockServer`] unless configured otherwise.
+pub const MOCK_USER_TOKEN: &str = "woodpecker-mock-token";
+
+/// The name of the mock user on replies posted through a [`super::MockServer`].
+pub const MOCK_USER_NAME: &str = "Alice";
+
+/// The number of holes on one `getlist` page, as served by pkuhelper.
+pub const FEED_PAGE_SIZE: usize = 30;
+
+/// The page size of `search` when the request does not specify one.
+pub const SEARCH_PAGE_SIZE: usize = 50;
+
+/// The server time reported by every fixture page.
+pub const FIXTURE_TIMESTAMP: i64 = 1_656_000_000;
+
+const FIXTURE_EPOCH: i64 = 1_400_000_000;
+
+const NAMES: [&str; 5] = ["Alice", "Bob", "Carol", "Dave", "Eve"];
+
+/// A reply served by a [`super::MockServer`].
+#[derive(Debug, Clone)]
+pub struct FixtureReply {
+    pub cid: usize,
+    pub name: String,
+    pub text: String,
+    pub dz: bool,
+    pub timestamp: i64,
+    pub tag: Option<String>,
+}
+
+/// A hole served by a [`super::MockServer`].
+#[derive(Debug, Clone)]
+pub struct FixtureHole {
+    pub pid: usize,
+    pub text: String,
+    /// `text`, `image` or `audio`.
+    pub kind: String,
+    /// The media url of an `image` or `audio` hole, empty otherwise.
+    pub url: String,
+    pub timestamp: i64,
+    pub likenum: usize,
+    pub tag: Option<String>,
+    /// Whether the mock user follows this hole.
+    pub attention: bool,
+    pub replies: Vec<FixtureReply>,
+}
+
+impl FixtureHole {
+    /// Returns a text hole without replies, posted `pid` minutes after a fixed epoch.
+    pub fn text(pid: usize, text: &str) -> Self {
+        FixtureHole {
+            pid,
+            text: String::from(text),
+            kind: String::from("text"),
+            url: String::new(),
+            timestamp: FIXTURE_EPOCH + 60 * pid as i64,
+            likenum: 0,
+            tag: None,
+            attention: false,
+            replies: Vec::new(),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "pid": self.pid.to_string(),
+            "text": self.text,
+            "type": self.kind,
+            "url": self.url,
+            "timestamp": self.timestamp.to_string(),
+            "reply": self.replies.len().to_string(),
+            "likenum": self.likenum.to_string(),
+            "tag": self.tag,
+        })
+    }
+}
+
+impl FixtureReply {
+    pub(crate) fn to_json(&self, pid: usize) -> Value {
+        json!({
+            "cid": self.cid.to_string(),
+            "pid": pid.to_string(),
+            "name": self.name,
+            "text": self.text,
+            "islz": usize::from(self.dz),
+            "timestamp": self.timestamp.to_string(),
+            "tag": self.tag,
+        })
+    }
+}
+
+/// The treehole content served by a [`super::MockServer`].
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// The token a request must carry, see [`MOCK_USER_TOKEN`].
+    pub user_token: String,
+    pub holes: Vec<FixtureHole>,
+    /// Media served under `images/` and `audios/`, keyed by the media url of holes.
+    pub media: BTreeMap<String, Vec<u8>>,
+}
+
+impl Default for Fixture {
+    /// Returns 100 generated holes, numbered from 1001 to 1100.
+    ///
+    /// Every third hole mentions `woodpecker`, every fifth is an image hole with a small
+    /// JPEG, and every 25th is followed by the mock user.
+    fn default() -> Self {
+        Fixture::generate(1001..=1100)
+    }
+}
+
+impl Fixture {
+    /// Returns an empty treehole.
+    pub fn empty() -> Self {
+        Fixture {
+            user_token: String::from(MOCK_USER_TOKEN),
+            holes: Vec::new(),
+            media: BTreeMap::new(),
+        }
+    }
+
+    /// Returns a treehole with a deterministic hole for every `pid`.
+    pub fn generate(pids: impl IntoIterator<Item = usize>) -> Self {
+        let mut media = BTreeMap::new();
+        let holes = pids
+            .into_iter()
+            .map(|pid| {
+                let text = if pid % 3 == 0 {
+                    format!("Hole {} about woodpecker.", pid)
+                } else {
+                    format!("Hole {}.", pid)
+                };
+                let mut hole = FixtureHole::text(pid, &text);
+                if pid % 5 == 0 {
+                    hole.kind = String::from("image");
+                    hole.url = format!("{}.jpeg", pid);
+                    let mut jpeg = b"\xFF\xD8\xFF\xE0".to_vec();
+                    jpeg.extend(format!("image of hole {}", pid).bytes());
+                    media.insert(hole.url.clone(), jpeg);
+                }
+                hole.likenum = pid % 7;
+                hole.attention = pid % 25 == 0;
+                hole.replies = (0..pid % 4)
+                    .map(|i| {
+                        let dz = i == 0;
+                        let name = if dz {
+                            String::from("洞主")
+                        } else {
+                            String::from(NAMES[(i - 1) % NAMES.len()])
+                        };
+                        FixtureReply {
+                            cid: pid * 100 + i,
+                            text: if dz {
+                                format!("Reply {} to hole {}.", i, pid)
+                            } else {
+                                format!("[洞主] Reply {} to hole {}.", i, pid)
+                            },
+                            name,
+                            dz,
+                            timestamp: hole.timestamp + 60 * (i as i64 + 1),
+                            tag: None,
+                        }
+                    })
+                    .collect();
+                hole
+            })
+            .collect();
+        Fixture {
+            holes,
+            media,
+            ..Fixture::empty()
+        }
+    }
+
+    /// Adds a hole, replacing any hole with the same `pid`.
+    pub fn with_hole(mut self, hole: FixtureHole) -> Self {
+        self.holes.retain(|h| h.pid != hole.pid);
+        self.holes.push(hole);
+        self
+    }
+
+    /// Serves `content` as the media `url`, replacing any media with the same url.
+    pub fn with_media(mut self, url: &str, content: &[u8]) -> Self {
+        self.media.insert(String::from(url), content.to_vec());
+        self
+    }
+
+    pub fn hole(&self, pid: usize) -> Option<&FixtureHole> {
+        self.holes.iter().find(|h| h.pid == pid)
+    }
+
+    /// Holes ordered from the newest to the oldest, as the feed lists them.
+    pub(crate) fn feed(&self) -> Vec<&FixtureHole> {
+        let mut holes = self.holes.iter().collect::<Vec<_>>();
+        holes.sort_by_key(|h| std::cmp::Reverse(h.pid));
+        holes
+    }
+}
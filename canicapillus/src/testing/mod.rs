@@ -0,0 +1,430 @@
+//! An offline treehole backend for deterministic tests.
+//!
+//! A [`MockServer`] listens on a local port and answers the read actions of
+//! pkuhelper (`getlist`, `getone`, `search`, `getcomment` and `getattention`)
+//! from a [`Fixture`], so the whole [`Fetcher`] pipeline can run without network access.
+//! The write actions `dopost`, `docomment` and `attention` modify the fixture, and
+//! the media of image and audio holes is served next to the api script.
+//!
+//! ```ignore
+//! let server = MockServer::start(Fixture::default()).await;
+//! let mut fetcher: Fetcher<API, HoleSet> = server.fetcher();
+//! let holes = fetcher.fetch(&FetchFeed).execute().await?;
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    net::{SocketAddr, TcpListener},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, HeaderMap, Method, Request, Response, Server, StatusCode,
+};
+use serde_json::{json, Value};
+use tokio::{sync::oneshot, time};
+use url::Url;
+
+use crate::{api::API, common::Endpoint, fetcher::Fetcher};
+
+mod fixture;
+pub use fixture::*;
+
+/// The path of the api script on a [`MockServer`], mirroring pkuhelper.
+pub const MOCK_API_PATH: &str = "/services/pkuhole/api.php";
+
+/// The paths of image and audio media on a [`MockServer`], next to [`MOCK_API_PATH`].
+const MOCK_MEDIA_DIRS: [&str; 2] = ["/services/pkuhole/images/", "/services/pkuhole/audios/"];
+
+struct MockState {
+    fixture: Mutex<Fixture>,
+    requests: Mutex<Vec<Url>>,
+    headers: Mutex<Vec<HeaderMap>>,
+    faults: Mutex<VecDeque<StatusCode>>,
+    latency: Mutex<Duration>,
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+/// A request being handled, counted in [`MockServer::peak_in_flight`].
+struct InFlight<'s>(&'s MockState);
+
+impl<'s> InFlight<'s> {
+    fn enter(state: &'s MockState) -> Self {
+        let in_flight = state.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        state.peak.fetch_max(in_flight, Ordering::SeqCst);
+        InFlight(state)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A local HTTP server imitating the hole backend API.
+///
+/// The server shuts down when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts serving `fixture` on a random local port.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no local port can be bound, or if not called within a tokio runtime.
+    pub async fn start(fixture: Fixture) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Cannot bind a local port.");
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let state = Arc::new(MockState {
+            fixture: Mutex::new(fixture),
+            requests: Mutex::new(Vec::new()),
+            headers: Mutex::new(Vec::new()),
+            faults: Mutex::new(VecDeque::new()),
+            latency: Mutex::new(Duration::ZERO),
+            in_flight: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        });
+        let make_service = {
+            let state = Arc::clone(&state);
+            make_service_fn(move |_| {
+                let state = Arc::clone(&state);
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |req| {
+                        let state = Arc::clone(&state);
+                        async move { Ok::<_, Infallible>(handle(&state, addr, req).await) }
+                    }))
+                }
+            })
+        };
+
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)
+            .unwrap()
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                signal.await.ok();
+            });
+        tokio::spawn(server);
+
+        MockServer {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// The base URL of the api script, to be passed to [`API::new`].
+    pub fn base(&self) -> String {
+        format!("http://{}{}", self.addr, MOCK_API_PATH)
+    }
+
+    /// Returns an [`API`] pointing at this server, authorized by the fixture token.
+    pub fn api(&self) -> API {
+        API::new(&self.base(), None, &self.fixture().user_token)
+    }
+
+    /// Returns a [`Fetcher`] requesting this server.
+    pub fn fetcher<R>(&self) -> Fetcher<API, R>
+    where
+        API: Endpoint<R>,
+    {
+        Fetcher::new(self.api())
+    }
+
+    /// The fixture served now, including the changes of write actions.
+    pub fn fixture(&self) -> Fixture {
+        self.state.fixture.lock().unwrap().clone()
+    }
+
+    /// Fails the next `count` requests with `status` and a non-JSON body, like a flaky gateway.
+    pub fn fail_next(&self, count: usize, status: StatusCode) {
+        self.state
+            .faults
+            .lock()
+            .unwrap()
+            .extend(std::iter::repeat(status).take(count));
+    }
+
+    /// Delays every later response by `latency`, like a slow backend.
+    pub fn set_latency(&self, latency: Duration) {
+        *self.state.latency.lock().unwrap() = latency;
+    }
+
+    /// The most requests handled at once so far.
+    pub fn peak_in_flight(&self) -> usize {
+        self.state.peak.load(Ordering::SeqCst)
+    }
+
+    /// All requests received so far, in arrival order.
+    pub fn requests(&self) -> Vec<Url> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// The headers of all requests received so far, in arrival order.
+    pub fn request_headers(&self) -> Vec<HeaderMap> {
+        self.state.headers.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+async fn handle(state: &MockState, addr: SocketAddr, req: Request<Body>) -> Response<Body> {
+    let url = Url::parse(&format!("http://{}{}", addr, req.uri())).unwrap();
+    state.requests.lock().unwrap().push(url.clone());
+    state.headers.lock().unwrap().push(req.headers().clone());
+
+    let _in_flight = InFlight::enter(state);
+    let latency = *state.latency.lock().unwrap();
+    if !latency.is_zero() {
+        time::sleep(latency).await;
+    }
+
+    let fault = state.faults.lock().unwrap().pop_front();
+    if let Some(status) = fault {
+        return Response::builder()
+            .status(status)
+            .header(hyper::header::CONTENT_TYPE, "text/html")
+            .body(Body::from(format!("<html><body>{}</body></html>", status)))
+            .unwrap();
+    }
+
+    let media = MOCK_MEDIA_DIRS
+        .iter()
+        .find_map(|dir| url.path().strip_prefix(dir));
+    if let Some(name) = media {
+        let fixture = state.fixture.lock().unwrap();
+        return match fixture.media.get(name) {
+            Some(content) => Response::new(Body::from(content.clone())),
+            None => respond(
+                StatusCode::NOT_FOUND,
+                json!({ "code": -1, "msg": "Not found." }),
+            ),
+        };
+    }
+
+    if url.path() != MOCK_API_PATH {
+        return respond(
+            StatusCode::NOT_FOUND,
+            json!({ "code": -1, "msg": "Not found." }),
+        );
+    }
+
+    let mut query = url.query_pairs().into_owned().collect::<HashMap<_, _>>();
+    let mut files = HashMap::new();
+    if req.method() == Method::POST {
+        let content_type = req
+            .headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        let body = hyper::body::to_bytes(req.into_body())
+            .await
+            .unwrap_or_default();
+        match content_type.split_once("boundary=") {
+            Some((_, boundary)) => {
+                for (name, filename, content) in multipart(&body, boundary) {
+                    match filename {
+                        Some(filename) => {
+                            files.insert(name, (filename, content));
+                        }
+                        None => {
+                            query.insert(name, String::from_utf8_lossy(&content).into_owned());
+                        }
+                    }
+                }
+            }
+            None => query.extend(url::form_urlencoded::parse(&body).into_owned()),
+        }
+    }
+    let param = |key: &str| query.get(key).map(String::as_str);
+    let number = |key: &str| param(key).and_then(|v| v.parse::<usize>().ok());
+
+    let mut fixture = state.fixture.lock().unwrap();
+    if param("user_token") != Some(fixture.user_token.as_str()) {
+        return respond(StatusCode::OK, json!({ "code": -1, "msg": "请先登录" }));
+    }
+
+    let body = match param("action") {
+        Some("dopost") => match param("text").unwrap_or_default() {
+            "" => json!({ "code": -1, "msg": "请输入内容" }),
+            text => {
+                let pid = fixture.holes.iter().map(|h| h.pid).max().unwrap_or(0) + 1;
+                let mut hole = FixtureHole::text(pid, text);
+                if let Some((filename, _)) = files.get("data") {
+                    hole.kind = String::from("image");
+                    hole.url = filename.clone();
+                }
+                fixture.holes.push(hole);
+                json!({ "code": 0, "data": pid })
+            }
+        },
+        Some("docomment") => {
+            let text = param("text").unwrap_or_default().to_owned();
+            let cid = fixture
+                .holes
+                .iter()
+                .flat_map(|h| h.replies.iter().map(|r| r.cid))
+                .max()
+                .unwrap_or(0)
+                + 1;
+            match number("pid").and_then(|pid| fixture.holes.iter_mut().find(|h| h.pid == pid)) {
+                Some(_) if text.is_empty() => json!({ "code": -1, "msg": "请输入内容" }),
+                Some(hole) => {
+                    let timestamp = hole.timestamp + 60 * (hole.replies.len() as i64 + 1);
+                    hole.replies.push(FixtureReply {
+                        cid,
+                        name: String::from(MOCK_USER_NAME),
+                        text,
+                        dz: false,
+                        timestamp,
+                        tag: None,
+                    });
+                    json!({ "code": 0, "data": cid })
+                }
+                None => json!({ "code": -1, "msg": "没有这条树洞" }),
+            }
+        }
+        Some("attention") => {
+            let follow = param("switch") == Some("1");
+            match number("pid").and_then(|pid| fixture.holes.iter_mut().find(|h| h.pid == pid)) {
+                Some(hole) => {
+                    hole.attention = follow;
+                    json!({ "code": 0, "msg": if follow { "已关注" } else { "已取消关注" } })
+                }
+                None => json!({ "code": -1, "msg": "没有这条树洞" }),
+            }
+        }
+        Some("getlist") => {
+            let page = number("p").unwrap_or(1).max(1);
+            let data = fixture
+                .feed()
+                .into_iter()
+                .skip((page - 1) * FEED_PAGE_SIZE)
+                .take(FEED_PAGE_SIZE)
+                .map(FixtureHole::to_json)
+                .collect::<Vec<_>>();
+            json!({ "code": 0, "data": data, "timestamp": FIXTURE_TIMESTAMP, "count": null })
+        }
+        Some("getone") => match number("pid").and_then(|pid| fixture.hole(pid)) {
+            Some(hole) => {
+                json!({ "code": 0, "data": hole.to_json(), "timestamp": FIXTURE_TIMESTAMP })
+            }
+            None => json!({ "code": -1, "msg": "没有这条树洞" }),
+        },
+        Some("search") => {
+            let keywords = param("keywords").unwrap_or_default();
+            let page = number("page").unwrap_or(1).max(1);
+            let page_size = number("pagesize").unwrap_or(SEARCH_PAGE_SIZE).max(1);
+            let found = fixture
+                .feed()
+                .into_iter()
+                .filter(|h| h.text.contains(keywords))
+                .collect::<Vec<_>>();
+            let data = found
+                .iter()
+                .skip((page - 1) * page_size)
+                .take(page_size)
+                .map(|h| h.to_json())
+                .collect::<Vec<_>>();
+            json!({ "code": 0, "data": data, "count": found.len() })
+        }
+        Some("getcomment") => match number("pid").and_then(|pid| fixture.hole(pid)) {
+            Some(hole) => {
+                let data = hole
+                    .replies
+                    .iter()
+                    .map(|r| r.to_json(hole.pid))
+                    .collect::<Vec<_>>();
+                json!({ "code": 0, "data": data, "attention": usize::from(hole.attention) })
+            }
+            None => json!({ "code": -1, "msg": "没有这条树洞" }),
+        },
+        Some("getattention") => {
+            let data = fixture
+                .feed()
+                .into_iter()
+                .filter(|h| h.attention)
+                .map(FixtureHole::to_json)
+                .collect::<Vec<Value>>();
+            json!({ "code": 0, "data": data })
+        }
+        _ => {
+            return respond(
+                StatusCode::BAD_REQUEST,
+                json!({ "code": -1, "msg": "Unknown action." }),
+            )
+        }
+    };
+
+    respond(StatusCode::OK, body)
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Splits a `multipart/form-data` body into its fields: name, file name and content.
+fn multipart(body: &[u8], boundary: &str) -> Vec<(String, Option<String>, Vec<u8>)> {
+    let boundary = boundary.trim_matches('"');
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut fields = Vec::new();
+    let mut rest = body;
+    while let Some(start) = find(rest, &delimiter) {
+        rest = &rest[start + delimiter.len()..];
+        if rest.starts_with(b"--") {
+            break;
+        }
+        let part = match find(rest, &delimiter) {
+            Some(end) => &rest[..end],
+            None => rest,
+        };
+        let Some(split) = find(part, b"\r\n\r\n") else {
+            continue;
+        };
+        let headers = String::from_utf8_lossy(&part[..split]);
+        let content = part[split + 4..]
+            .strip_suffix(b"\r\n")
+            .unwrap_or(&part[split + 4..]);
+        let attribute = |key: &str| {
+            let pattern = format!("{}=\"", key);
+            let start = headers.find(&pattern)? + pattern.len();
+            let end = headers[start..].find('"')? + start;
+            Some(headers[start..end].to_owned())
+        };
+        if let Some(name) = attribute(" name") {
+            fields.push((name, attribute("filename"), content.to_vec()));
+        }
+    }
+    fields
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
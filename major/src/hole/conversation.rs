@@ -0,0 +1,231 @@
+//! Nested discussions under a hole, rebuilt from whom every reply answers.
+//!
+//! A reply addressed to `[Alice]` answers the latest earlier reply by Alice. A reply
+//! addressed to nobody, or to a pseudonym without an earlier reply, answers the hole
+//! itself and starts a new branch.
+
+use std::collections::BTreeMap;
+
+use super::reply::{Reply, ReplyEntry, ReplyID};
+
+/// A reply in a [`Conversation`], with the replies answering it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationNode {
+    pub reply: Reply,
+    /// The reply this one answers, `None` if it answers the hole.
+    pub parent: Option<ReplyID>,
+    /// Replies answering this one, ordered by id.
+    pub children: Vec<ReplyID>,
+}
+
+/// The replies of a hole as a forest, each tree being a branch of the discussion.
+///
+/// ```ignore
+/// let conversation = Conversation::from_entries(&replies);
+/// for (depth, reply) in conversation.walk() {
+///     println!("{}{}: {}", "  ".repeat(depth), reply.name, reply.text);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conversation {
+    nodes: BTreeMap<ReplyID, ConversationNode>,
+    roots: Vec<ReplyID>,
+}
+
+impl Conversation {
+    /// Builds the conversation of `replies`, ordered by id regardless of the given order.
+    ///
+    /// Of replies sharing an id, the last one is kept.
+    pub fn new(replies: impl IntoIterator<Item = Reply>) -> Self {
+        let replies = replies
+            .into_iter()
+            .map(|reply| (reply.id, reply))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut conversation = Conversation::default();
+        // the latest reply of every pseudonym so far
+        let mut latest = BTreeMap::<String, ReplyID>::new();
+        for (id, reply) in replies {
+            let parent = reply
+                .reply_to
+                .as_ref()
+                .and_then(|name| latest.get(name))
+                .copied();
+            match parent {
+                Some(parent) => conversation
+                    .nodes
+                    .get_mut(&parent)
+                    .unwrap()
+                    .children
+                    .push(id),
+                None => conversation.roots.push(id),
+            }
+            latest.insert(reply.name.clone(), id);
+            conversation.nodes.insert(
+                id,
+                ConversationNode {
+                    reply,
+                    parent,
+                    children: Vec::new(),
+                },
+            );
+        }
+        conversation
+    }
+
+    /// Builds the conversation of the newest snapshot of every reply in `entries`.
+    pub fn from_entries<'r>(entries: impl IntoIterator<Item = &'r ReplyEntry>) -> Self {
+        let mut newest = BTreeMap::<ReplyID, &ReplyEntry>::new();
+        for entry in entries {
+            let kept = newest.entry(entry.entry.id).or_insert(entry);
+            if entry.snapshot > kept.snapshot {
+                *kept = entry;
+            }
+        }
+        Self::new(newest.into_values().map(|entry| entry.entry.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, id: ReplyID) -> Option<&ConversationNode> {
+        self.nodes.get(&id)
+    }
+
+    /// Replies answering the hole itself, ordered by id.
+    pub fn roots(&self) -> impl Iterator<Item = &ConversationNode> {
+        self.roots.iter().map(|id| &self.nodes[id])
+    }
+
+    /// Replies answering the reply `id`, ordered by id.
+    pub fn children(&self, id: ReplyID) -> impl Iterator<Item = &ConversationNode> {
+        self.nodes
+            .get(&id)
+            .into_iter()
+            .flat_map(|node| node.children.iter().map(|child| &self.nodes[child]))
+    }
+
+    /// The replies from the root of its branch down to the reply `id`, empty if absent.
+    pub fn ancestry(&self, id: ReplyID) -> Vec<&Reply> {
+        let mut ancestry = Vec::new();
+        let mut next = self.nodes.get(&id);
+        while let Some(node) = next {
+            ancestry.push(&node.reply);
+            next = node.parent.map(|parent| &self.nodes[&parent]);
+        }
+        ancestry.reverse();
+        ancestry
+    }
+
+    /// Every reply in depth-first order, with its depth counting from 0 at the roots.
+    pub fn walk(&self) -> Vec<(usize, &Reply)> {
+        let mut walked = Vec::with_capacity(self.nodes.len());
+        let mut stack = self
+            .roots
+            .iter()
+            .rev()
+            .map(|id| (0, *id))
+            .collect::<Vec<_>>();
+        while let Some((depth, id)) = stack.pop() {
+            let node = &self.nodes[&id];
+            walked.push((depth, &node.reply));
+            stack.extend(node.children.iter().rev().map(|child| (depth + 1, *child)));
+        }
+        walked
+    }
+
+    /// How many times each pseudonym answers each other pseudonym, keyed by
+    /// `(speaker, addressee)`.
+    ///
+    /// Replies answering the hole itself are left out.
+    pub fn interactions(&self) -> BTreeMap<(String, String), usize> {
+        let mut interactions = BTreeMap::new();
+        for node in self.nodes.values() {
+            if let Some(parent) = node.parent {
+                let key = (
+                    node.reply.name.clone(),
+                    self.nodes[&parent].reply.name.clone(),
+                );
+                *interactions.entry(key).or_insert(0) += 1;
+            }
+        }
+        interactions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hole::reply::{split_people_prefix, test_reply as reply};
+
+    #[test]
+    fn split_prefix() {
+        assert_eq!(
+            split_people_prefix("[洞主] Hello."),
+            (Some(String::from("洞主")), "Hello.")
+        );
+        assert_eq!(
+            split_people_prefix("[Angry Alice] Hi."),
+            (Some(String::from("Angry Alice")), "Hi.")
+        );
+        assert_eq!(
+            split_people_prefix("Quoting [Bob] here."),
+            (None, "Quoting [Bob] here.")
+        );
+    }
+
+    #[test]
+    fn build_conversation() {
+        let conversation = Conversation::new([
+            reply(5, "Bob", "[Alice] Agreed."),
+            reply(1, "Alice", "[洞主] First."),
+            reply(2, "洞主", "[Alice] Thanks."),
+            reply(3, "Carol", "Unrelated."),
+            reply(4, "Alice", "[洞主] You are welcome."),
+            reply(6, "Dave", "[Eve] Nobody here."),
+        ]);
+        assert_eq!(conversation.len(), 6);
+        let roots = conversation
+            .roots()
+            .map(|n| n.reply.id.0)
+            .collect::<Vec<_>>();
+        assert_eq!(roots, [1, 3, 6]);
+        assert_eq!(
+            conversation.get(ReplyID(4)).unwrap().parent,
+            Some(ReplyID(2))
+        );
+        assert_eq!(
+            conversation.get(ReplyID(5)).unwrap().parent,
+            Some(ReplyID(4))
+        );
+
+        let walked = conversation
+            .walk()
+            .into_iter()
+            .map(|(depth, reply)| (depth, reply.id.0))
+            .collect::<Vec<_>>();
+        assert_eq!(walked, [(0, 1), (1, 2), (2, 4), (3, 5), (0, 3), (0, 6)]);
+
+        let ancestry = conversation.ancestry(ReplyID(5));
+        assert_eq!(
+            ancestry.iter().map(|r| r.id.0).collect::<Vec<_>>(),
+            [1, 2, 4, 5]
+        );
+
+        let interactions = conversation.interactions();
+        assert_eq!(
+            interactions[&(String::from("Alice"), String::from("洞主"))],
+            1
+        );
+        assert_eq!(
+            interactions[&(String::from("洞主"), String::from("Alice"))],
+            1
+        );
+        assert_eq!(interactions.len(), 3);
+    }
+}
@@ -0,0 +1,363 @@
+//! Snapshots of the same hole across fetches.
+//!
+//! [`Hole`](super::Hole)s compare by id only, so a [`BTreeSet`] of [`HoleEntry`]s keeps
+//! the first copy among copies of the same snapshot, and piles up copies of different
+//! snapshots. This module keeps the newest snapshot, or every snapshot in order, and
+//! reports what changes between them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+
+use super::{HoleEntry, HoleID, HoleKind};
+
+/// A field of a hole changing between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HoleChange {
+    Text {
+        before: String,
+        after: String,
+    },
+    Kind {
+        before: HoleKind,
+        after: HoleKind,
+    },
+    Reply {
+        before: usize,
+        after: usize,
+    },
+    Likenum {
+        before: usize,
+        after: usize,
+    },
+    Tag {
+        before: Option<String>,
+        after: Option<String>,
+    },
+}
+
+/// The changes of a hole between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoleDiff {
+    pub id: HoleID,
+    pub before: DateTime<Utc>,
+    pub after: DateTime<Utc>,
+    /// Changed fields, in the order of [`HoleChange`] variants.
+    pub changes: Vec<HoleChange>,
+}
+
+impl HoleDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The number of likes gained, negative if likes are withdrawn.
+    pub fn likes_gained(&self) -> i64 {
+        self.changes
+            .iter()
+            .find_map(|change| match change {
+                HoleChange::Likenum { before, after } => Some(*after as i64 - *before as i64),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    /// The number of replies gained, negative if replies are deleted.
+    pub fn replies_gained(&self) -> i64 {
+        self.changes
+            .iter()
+            .find_map(|change| match change {
+                HoleChange::Reply { before, after } => Some(*after as i64 - *before as i64),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Reports what changes from `before` to `after`, or `None` if they are different holes.
+pub fn diff(before: &HoleEntry, after: &HoleEntry) -> Option<HoleDiff> {
+    let (old, new) = (&before.entry, &after.entry);
+    if old.id != new.id {
+        return None;
+    }
+
+    let mut changes = Vec::new();
+    if old.text != new.text {
+        changes.push(HoleChange::Text {
+            before: old.text.clone(),
+            after: new.text.clone(),
+        });
+    }
+    if old.kind != new.kind {
+        changes.push(HoleChange::Kind {
+            before: old.kind.clone(),
+            after: new.kind.clone(),
+        });
+    }
+    if old.reply != new.reply {
+        changes.push(HoleChange::Reply {
+            before: old.reply,
+            after: new.reply,
+        });
+    }
+    if old.likenum != new.likenum {
+        changes.push(HoleChange::Likenum {
+            before: old.likenum,
+            after: new.likenum,
+        });
+    }
+    if old.tag != new.tag {
+        changes.push(HoleChange::Tag {
+            before: old.tag.clone(),
+            after: new.tag.clone(),
+        });
+    }
+
+    Some(HoleDiff {
+        id: old.id,
+        before: before.snapshot,
+        after: after.snapshot,
+        changes,
+    })
+}
+
+/// Inserts `entry` unless `set` holds a newer snapshot of the same hole,
+/// removing any older snapshot.
+///
+/// Returns whether `entry` is kept.
+pub fn keep_newest(set: &mut BTreeSet<HoleEntry>, entry: HoleEntry) -> bool {
+    // entries order by id, then by snapshot
+    let id = entry.entry.id;
+    if set
+        .range(&entry..)
+        .take_while(|e| e.entry.id == id)
+        .any(|e| e.snapshot > entry.snapshot)
+    {
+        return false;
+    }
+    let older = set
+        .range(..&entry)
+        .rev()
+        .take_while(|e| e.entry.id == id)
+        .cloned()
+        .collect::<Vec<_>>();
+    for e in older {
+        set.remove(&e);
+    }
+    set.replace(entry);
+    true
+}
+
+/// Merges two sets, keeping the newest snapshot of every hole, even if either set holds
+/// several snapshots of a hole.
+///
+/// On a tie, the snapshot of `rhs` is kept.
+pub fn merge_newest(lhs: BTreeSet<HoleEntry>, rhs: BTreeSet<HoleEntry>) -> BTreeSet<HoleEntry> {
+    let mut merged = BTreeSet::new();
+    for entry in lhs.into_iter().chain(rhs) {
+        keep_newest(&mut merged, entry);
+    }
+    merged
+}
+
+/// Every snapshot of a hole, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoleTimeline {
+    snapshots: Vec<HoleEntry>,
+}
+
+impl HoleTimeline {
+    pub fn new(entry: HoleEntry) -> Self {
+        HoleTimeline {
+            snapshots: vec![entry],
+        }
+    }
+
+    pub fn id(&self) -> HoleID {
+        self.snapshots[0].entry.id
+    }
+
+    /// Records a snapshot, replacing any snapshot taken at the same time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entry` is another hole.
+    pub fn push(&mut self, entry: HoleEntry) {
+        assert_eq!(entry.entry.id, self.id(), "A timeline holds a single hole.");
+        match self
+            .snapshots
+            .binary_search_by_key(&entry.snapshot, |e| e.snapshot)
+        {
+            Ok(i) => self.snapshots[i] = entry,
+            Err(i) => self.snapshots.insert(i, entry),
+        }
+    }
+
+    pub fn snapshots(&self) -> &[HoleEntry] {
+        &self.snapshots
+    }
+
+    pub fn earliest(&self) -> &HoleEntry {
+        &self.snapshots[0]
+    }
+
+    pub fn latest(&self) -> &HoleEntry {
+        self.snapshots.last().unwrap()
+    }
+
+    /// The changes between every two consecutive snapshots, skipping unchanged ones.
+    pub fn diffs(&self) -> impl Iterator<Item = HoleDiff> + '_ {
+        self.snapshots
+            .windows(2)
+            .filter_map(|pair| diff(&pair[0], &pair[1]))
+            .filter(|diff| !diff.is_empty())
+    }
+
+    /// The changes from the earliest to the latest snapshot.
+    pub fn overall(&self) -> HoleDiff {
+        diff(self.earliest(), self.latest()).unwrap()
+    }
+}
+
+/// The timelines of many holes.
+///
+/// ```ignore
+/// let mut history = HoleHistory::default();
+/// loop {
+///     history.record(fetcher.fetch(&FetchFeed).execute().await?);
+///     dbg!(history.trending().take(10).collect::<Vec<_>>());
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HoleHistory {
+    timelines: BTreeMap<HoleID, HoleTimeline>,
+}
+
+impl HoleHistory {
+    /// Records snapshots of any holes.
+    pub fn record(&mut self, entries: impl IntoIterator<Item = HoleEntry>) {
+        for entry in entries {
+            match self.timelines.get_mut(&entry.entry.id) {
+                Some(timeline) => timeline.push(entry),
+                None => {
+                    self.timelines
+                        .insert(entry.entry.id, HoleTimeline::new(entry));
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, id: HoleID) -> Option<&HoleTimeline> {
+        self.timelines.get(&id)
+    }
+
+    pub fn timelines(&self) -> impl Iterator<Item = &HoleTimeline> {
+        self.timelines.values()
+    }
+
+    /// The latest snapshot of every hole.
+    pub fn latest(&self) -> BTreeSet<HoleEntry> {
+        self.timelines
+            .values()
+            .map(|timeline| timeline.latest().clone())
+            .collect()
+    }
+
+    /// The overall changes of holes gaining likes, the most gaining first.
+    pub fn trending(&self) -> impl Iterator<Item = HoleDiff> {
+        let mut diffs = self
+            .timelines
+            .values()
+            .map(HoleTimeline::overall)
+            .filter(|diff| diff.likes_gained() > 0)
+            .collect::<Vec<_>>();
+        diffs.sort_by_key(|diff| (std::cmp::Reverse(diff.likes_gained()), diff.id));
+        diffs.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::hole::Hole;
+
+    fn entry(id: usize, likenum: usize, reply: usize, snapshot: i64) -> HoleEntry {
+        HoleEntry {
+            entry: Hole {
+                id: HoleID(id),
+                text: format!("Hole {}.", id),
+                kind: HoleKind::Text,
+                timestamp: Utc.timestamp_opt(1_600_000_000, 0).unwrap(),
+                reply,
+                likenum,
+                tag: None,
+            },
+            snapshot: Utc.timestamp_opt(snapshot, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn diff_snapshots() {
+        let mut after = entry(1, 5, 2, 2000);
+        after.entry.tag = Some(String::from("tag"));
+        let diff = diff(&entry(1, 1, 2, 1000), &after).unwrap();
+        assert_eq!(
+            diff.changes,
+            [
+                HoleChange::Likenum {
+                    before: 1,
+                    after: 5
+                },
+                HoleChange::Tag {
+                    before: None,
+                    after: Some(String::from("tag"))
+                },
+            ]
+        );
+        assert_eq!(diff.likes_gained(), 4);
+        assert_eq!(diff.replies_gained(), 0);
+        assert!(super::diff(&entry(1, 1, 2, 1000), &entry(2, 1, 2, 1000)).is_none());
+    }
+
+    #[test]
+    fn keep_newest_snapshot() {
+        let lhs = BTreeSet::from([
+            entry(1, 5, 0, 2000),
+            entry(2, 0, 0, 1000),
+            entry(2, 1, 0, 1500),
+        ]);
+        let rhs = BTreeSet::from([entry(1, 1, 0, 1000), entry(2, 3, 0, 2000)]);
+        let merged = merge_newest(lhs, rhs)
+            .into_iter()
+            .map(|e| e.entry.likenum)
+            .collect::<Vec<_>>();
+        assert_eq!(merged, [5, 3]);
+
+        // stale snapshots first, in a set not built by `merge_newest`
+        let stale = BTreeSet::from([entry(3, 1, 0, 1000), entry(3, 2, 0, 2000)]);
+        let merged = merge_newest(stale, BTreeSet::new());
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged.first().unwrap().entry.likenum, 2);
+    }
+
+    #[test]
+    fn record_timelines() {
+        let mut history = HoleHistory::default();
+        history.record([entry(1, 1, 0, 2000), entry(2, 4, 1, 1000)]);
+        history.record([entry(1, 0, 0, 1000), entry(2, 4, 1, 2000)]);
+        history.record([entry(1, 3, 1, 3000), entry(3, 9, 0, 3000)]);
+
+        let timeline = history.get(HoleID(1)).unwrap();
+        assert_eq!(timeline.snapshots().len(), 3);
+        assert_eq!(timeline.latest().entry.likenum, 3);
+        assert_eq!(timeline.diffs().count(), 2);
+        assert_eq!(history.get(HoleID(2)).unwrap().diffs().count(), 0);
+        assert_eq!(
+            history.trending().map(|d| d.id).collect::<Vec<_>>(),
+            [HoleID(1)]
+        );
+        assert_eq!(history.latest().len(), 3);
+    }
+}
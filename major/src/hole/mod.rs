@@ -7,7 +7,11 @@ use crate::util::{lossy_deserialize_usize, OneOrMany};
 
 // use crate::common::{MergeResource, ParseResource, ParseResourceError, Resource};
 
+pub mod conversation;
+pub mod history;
 pub mod reply;
+pub mod speaker;
+pub mod text;
 
 #[derive(Debug, Deserialize)]
 pub struct RawHoleID(#[serde(deserialize_with = "lossy_deserialize_usize")] pub usize);
@@ -39,7 +43,7 @@ impl From<HoleID> for String {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum HoleKind {
     Text,
@@ -63,7 +67,7 @@ pub struct RawHole {
     pub tag: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Eq, Serialize)]
+#[derive(Debug, Clone, Deserialize, Eq, Serialize)]
 pub struct Hole {
     pub id: HoleID,
     pub text: String,
@@ -106,7 +110,7 @@ impl PartialEq for Hole {
 
 impl PartialOrd for Hole {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.id.partial_cmp(&other.id)
+        Some(self.cmp(other))
     }
 }
 
@@ -126,6 +130,8 @@ impl Hash for Hole {
 pub struct RawHolePage {
     pub code: i32,
     #[serde(default)]
+    pub msg: Option<String>,
+    #[serde(default)]
     pub count: Option<i32>,
     pub data: OneOrMany<RawHole>,
     #[serde(
@@ -137,7 +143,7 @@ pub struct RawHolePage {
 
 // derive_set!{ hole, Hole, HoleEntry, RawHolePage, HoleSet, HoleList }
 
-#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct HoleEntry {
     pub entry: Hole,
     #[serde(with = "crate::util::local_timestamp")]
@@ -2,7 +2,7 @@ use std::hash::Hash;
 
 use chrono::{DateTime, Utc, SubsecRound};
 use once_cell::sync::Lazy;
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::util::lossy_deserialize_usize;
 
@@ -45,7 +45,6 @@ pub struct RawReply {
     #[serde(rename = "pid")]
     pub hole: RawHoleID,
     pub name: String,
-    #[serde(deserialize_with = "strip_people_prefix")]
     pub text: String,
     #[serde(rename = "islz", deserialize_with = "crate::util::number_to_bool")]
     pub dz: bool,
@@ -54,11 +53,15 @@ pub struct RawReply {
     pub tag: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Eq)]
+#[derive(Debug, Clone, Deserialize, Serialize, Eq)]
 pub struct Reply {
     pub id: ReplyID,
     pub hole: HoleID,
     pub name: String,
+    /// The pseudonym this reply answers, parsed from the `[洞主]`/`[Alice]` prefix of the text.
+    #[serde(default)]
+    pub reply_to: Option<String>,
+    /// The text without the addressee prefix.
     pub text: String,
     pub dz: bool,
     pub timestamp: DateTime<Utc>,
@@ -68,8 +71,9 @@ pub struct Reply {
 impl From<RawReply> for Reply {
     fn from(raw: RawReply) -> Self {
         let RawReply { id, hole, name, text, dz, timestamp, tag } = raw;
-        let timestamp = DateTime::from(timestamp);
-        Self { id: id.into(), hole: hole.into(), name, text, dz, timestamp, tag }
+        let (reply_to, text) = split_people_prefix(&text);
+        let text = String::from(text);
+        Self { id: id.into(), hole: hole.into(), name, reply_to, text, dz, timestamp, tag }
     }
 }
 
@@ -81,7 +85,7 @@ impl PartialEq for Reply {
 
 impl PartialOrd for Reply {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.id.partial_cmp(&other.id)
+        Some(self.cmp(other))
     }
 }
 
@@ -102,13 +106,15 @@ pub struct ReplyFlag(bool);
 
 #[derive(Debug, Deserialize)]
 pub struct RawReplyPage {
-    pub code: usize,
+    pub code: i32,
+    #[serde(default)]
+    pub msg: Option<String>,
     pub data: Vec<RawReply>,
     #[serde(deserialize_with = "crate::util::number_to_bool")]
     pub attention: bool,
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct ReplyEntry {
     pub entry: Reply,
     #[serde(with = "crate::util::local_timestamp")]
@@ -139,12 +145,36 @@ where
     Ok(s.parse::<usize>().unwrap_or(0) == 1)
 } */
 
-static PEOPLE_PREFIX: Lazy<regex::Regex> = Lazy::new(|| regex::RegexBuilder::new(r#"\[(洞主|\w+?(\s\w+)?)\]\s+"#).build().unwrap());
+static PEOPLE_PREFIX: Lazy<regex::Regex> = Lazy::new(|| regex::RegexBuilder::new(r#"^\[(洞主|\w+?(\s\w+)?)\]\s+"#).build().unwrap());
+
+/// Splits the leading `[洞主]`/`[Alice]` addressee marker off a reply text, returning
+/// the addressee and the rest of the text.
+///
+/// Only a marker at the very start counts, so bracketed names quoted later are kept.
+pub fn split_people_prefix(text: &str) -> (Option<String>, &str) {
+    match PEOPLE_PREFIX.captures(text) {
+        Some(captures) => {
+            let whole = captures.get(0).unwrap();
+            (Some(String::from(&captures[1])), &text[whole.end()..])
+        }
+        None => (None, text),
+    }
+}
 
-fn strip_people_prefix<'de, D>(d: D) -> Result<String, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(d)?;
-    Ok(String::from(PEOPLE_PREFIX.replace(&s, "")))
+/// A reply to the hole 1 by `name`, posted `id` seconds after a fixed epoch, for tests.
+#[cfg(test)]
+pub(crate) fn test_reply(id: usize, name: &str, text: &str) -> Reply {
+    use chrono::TimeZone;
+
+    let (reply_to, text) = split_people_prefix(text);
+    Reply {
+        id: ReplyID(id),
+        hole: HoleID(1),
+        name: String::from(name),
+        reply_to,
+        text: String::from(text),
+        dz: name == "洞主",
+        timestamp: Utc.timestamp_opt(1_600_000_000 + id as i64, 0).unwrap(),
+        tag: None,
+    }
 }
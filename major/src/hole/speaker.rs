@@ -0,0 +1,288 @@
+//! Pseudonyms of the people replying to a hole.
+//!
+//! pkuhelper names the poster of a hole 洞主, and everybody else in order of their first
+//! reply: Alice, Bob, …, Zach, then Angry Alice, …, Angry Zach, Baby Alice, and so on.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::reply::{Reply, ReplyEntry, ReplyID};
+
+/// The name of the poster of a hole.
+pub const DZ_NAME: &str = "洞主";
+
+/// Names of the first 26 people replying to a hole.
+pub const NAMES: [&str; 26] = [
+    "Alice", "Bob", "Carol", "Dave", "Eve", "Francis", "Grace", "Hans", "Isabella", "Jason",
+    "Kate", "Louis", "Margaret", "Nathan", "Olivia", "Paul", "Queen", "Richard", "Susan", "Thomas",
+    "Uma", "Vivian", "Winnie", "Xander", "Yasmine", "Zach",
+];
+
+/// Prefixes of [`NAMES`] naming the people after the first 26.
+pub const PREFIXES: [&str; 26] = [
+    "Angry",
+    "Baby",
+    "Crazy",
+    "Diligent",
+    "Excited",
+    "Fat",
+    "Greedy",
+    "Hungry",
+    "Interesting",
+    "Japanese",
+    "Kind",
+    "Little",
+    "Magic",
+    "Naïve",
+    "Old",
+    "Powerful",
+    "Quiet",
+    "Rich",
+    "Superman",
+    "THU",
+    "Undefined",
+    "Valuable",
+    "Wifeless",
+    "Xiangbuchulai",
+    "Young",
+    "Zombie",
+];
+
+/// The number of pseudonyms of the scheme, bare names and prefixed ones.
+const ALIASES: usize = NAMES.len() * (PREFIXES.len() + 1);
+
+/// Who speaks in a reply, ordered as pkuhelper names them: 洞主, then aliases by ordinal,
+/// then names outside the scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Speaker {
+    /// The poster of the hole.
+    Dz,
+    /// The `n`-th other person replying, counting from 0 for Alice.
+    Alias(usize),
+    /// A name outside the scheme.
+    Other(String),
+}
+
+impl Speaker {
+    /// Parses a pseudonym, or an alias past the scheme written as `#n`, falling back to
+    /// [`Speaker::Other`].
+    pub fn parse(name: &str) -> Self {
+        let name = name.trim();
+        if name == DZ_NAME {
+            return Speaker::Dz;
+        }
+        if let Some(Ok(ordinal)) = name.strip_prefix('#').map(str::parse) {
+            return Speaker::Alias(ordinal);
+        }
+        let ordinal = match name.split_once(' ') {
+            None => NAMES.iter().position(|n| *n == name),
+            Some((prefix, name)) => PREFIXES
+                .iter()
+                .position(|p| *p == prefix)
+                .zip(NAMES.iter().position(|n| *n == name))
+                .map(|(prefix, name)| (prefix + 1) * NAMES.len() + name),
+        };
+        match ordinal {
+            Some(ordinal) => Speaker::Alias(ordinal),
+            None => Speaker::Other(String::from(name)),
+        }
+    }
+
+    pub fn is_dz(&self) -> bool {
+        matches!(self, Speaker::Dz)
+    }
+
+    /// The ordinal of an alias, e.g. 0 for Alice and 26 for Angry Alice.
+    pub fn ordinal(&self) -> Option<usize> {
+        match self {
+            Speaker::Alias(ordinal) => Some(*ordinal),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Speaker {
+    /// Writes the pseudonym, or `#n` for an alias past the scheme, both parsed back by
+    /// [`Speaker::parse`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Speaker::Dz => f.write_str(DZ_NAME),
+            Speaker::Alias(ordinal) if *ordinal < NAMES.len() => f.write_str(NAMES[*ordinal]),
+            Speaker::Alias(ordinal) if *ordinal < ALIASES => write!(
+                f,
+                "{} {}",
+                PREFIXES[ordinal / NAMES.len() - 1],
+                NAMES[ordinal % NAMES.len()]
+            ),
+            Speaker::Alias(ordinal) => write!(f, "#{}", ordinal),
+            Speaker::Other(name) => f.write_str(name),
+        }
+    }
+}
+
+impl FromStr for Speaker {
+    type Err = std::convert::Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(Speaker::parse(name))
+    }
+}
+
+impl Reply {
+    /// Who speaks in the reply, 洞主 whenever `dz` is set.
+    pub fn speaker(&self) -> Speaker {
+        if self.dz {
+            Speaker::Dz
+        } else {
+            Speaker::parse(&self.name)
+        }
+    }
+
+    /// Whom the reply answers, see [`Reply::reply_to`].
+    pub fn addressee(&self) -> Option<Speaker> {
+        self.reply_to.as_deref().map(Speaker::parse)
+    }
+}
+
+/// What a speaker does in a thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Participant {
+    pub speaker: Speaker,
+    /// Replies of the speaker, ordered by id.
+    pub replies: Vec<ReplyID>,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+}
+
+/// The speakers of a thread, ordered as [`Speaker`]s.
+///
+/// The position of a speaker shifts whenever a speaker ordered before it joins the thread,
+/// so color speakers by [`Speaker::ordinal`] to keep their colors as the thread grows.
+///
+/// ```ignore
+/// let participants = Participants::from_entries(&replies);
+/// for participant in participants.iter() {
+///     dbg!(participant.speaker.to_string(), participant.replies.len());
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Participants {
+    speakers: BTreeMap<Speaker, Participant>,
+}
+
+impl Participants {
+    /// Indexes `replies`, counting each reply id once.
+    pub fn new<'r>(replies: impl IntoIterator<Item = &'r Reply>) -> Self {
+        let replies = replies
+            .into_iter()
+            .map(|reply| (reply.id, reply))
+            .collect::<BTreeMap<_, _>>();
+        let mut participants = Participants::default();
+        for (id, reply) in replies {
+            let speaker = reply.speaker();
+            participants
+                .speakers
+                .entry(speaker.clone())
+                .and_modify(|participant| {
+                    participant.replies.push(id);
+                    participant.first = participant.first.min(reply.timestamp);
+                    participant.last = participant.last.max(reply.timestamp);
+                })
+                .or_insert_with(|| Participant {
+                    speaker,
+                    replies: vec![id],
+                    first: reply.timestamp,
+                    last: reply.timestamp,
+                });
+        }
+        participants
+    }
+
+    /// Indexes the replies of `entries`, regardless of their snapshots.
+    pub fn from_entries<'r>(entries: impl IntoIterator<Item = &'r ReplyEntry>) -> Self {
+        Self::new(entries.into_iter().map(|entry| &entry.entry))
+    }
+
+    pub fn len(&self) -> usize {
+        self.speakers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.speakers.is_empty()
+    }
+
+    pub fn get(&self, speaker: &Speaker) -> Option<&Participant> {
+        self.speakers.get(speaker)
+    }
+
+    /// The position of `speaker` in the index, shifting as speakers ordered before it join.
+    pub fn position(&self, speaker: &Speaker) -> Option<usize> {
+        self.speakers.keys().position(|s| s == speaker)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Participant> {
+        self.speakers.values()
+    }
+
+    /// Participants by the number of their replies, most first, ties in index order.
+    pub fn most_active(&self) -> Vec<&Participant> {
+        let mut participants = self.speakers.values().collect::<Vec<_>>();
+        participants.sort_by_key(|participant| std::cmp::Reverse(participant.replies.len()));
+        participants
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::hole::reply::test_reply;
+
+    #[test]
+    fn parse_speakers() {
+        assert_eq!(Speaker::parse("洞主"), Speaker::Dz);
+        assert_eq!(Speaker::parse("Alice"), Speaker::Alias(0));
+        assert_eq!(Speaker::parse("Zach"), Speaker::Alias(25));
+        assert_eq!(Speaker::parse("Angry Alice"), Speaker::Alias(26));
+        assert_eq!(Speaker::parse("Zombie Zach"), Speaker::Alias(ALIASES - 1));
+        assert_eq!(
+            Speaker::parse("You Win"),
+            Speaker::Other(String::from("You Win"))
+        );
+        assert_eq!(Speaker::parse("#3"), Speaker::Alias(3));
+        assert_eq!(Speaker::parse("#-1"), Speaker::Other(String::from("#-1")));
+        for ordinal in [0, 25, 26, 27, 100, ALIASES - 1, ALIASES, ALIASES + 1] {
+            let speaker = Speaker::Alias(ordinal);
+            assert_eq!(Speaker::parse(&speaker.to_string()), speaker);
+        }
+
+        let mut speakers = ["Bob", "Angry Alice", "You Win", "洞主", "Alice"]
+            .map(Speaker::parse)
+            .to_vec();
+        speakers.sort();
+        let names = speakers.iter().map(Speaker::to_string).collect::<Vec<_>>();
+        assert_eq!(names, ["洞主", "Alice", "Bob", "Angry Alice", "You Win"]);
+    }
+
+    #[test]
+    fn index_participants() {
+        let reply = |id: usize, name: &str| test_reply(id, name, "");
+        let replies = [
+            reply(3, "Bob"),
+            reply(1, "Alice"),
+            reply(2, "洞主"),
+            reply(4, "Alice"),
+        ];
+        let participants = Participants::new(&replies);
+        assert_eq!(participants.len(), 3);
+        assert_eq!(participants.position(&Speaker::Dz), Some(0));
+        assert_eq!(participants.position(&Speaker::Alias(1)), Some(2));
+        let alice = participants.get(&Speaker::Alias(0)).unwrap();
+        assert_eq!(alice.replies, [ReplyID(1), ReplyID(4)]);
+        assert_eq!(alice.last, Utc.timestamp_opt(1_600_000_004, 0).unwrap());
+        assert_eq!(participants.most_active()[0].speaker, Speaker::Alias(0));
+    }
+}
@@ -0,0 +1,228 @@
+//! Rich text of holes and replies: citations of other holes, links and mentions.
+//!
+//! Users cite a hole as `#1234567`, paste links as they are, and mention a speaker as
+//! `[Alice]` anywhere in a text. [`tokenize`] splits a text into these and the plain
+//! spans in between, keeping the byte offsets of every token.
+
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{reply::Reply, speaker::Speaker, Hole, HoleID};
+
+/// Links, citations and mentions, tried in this order at every position.
+static RICH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?P<url>https?://[!-~]+)|#(?P<pid>[0-9]+)|\[(?P<name>洞主|\p{L}+(?: \p{L}+)?)\]")
+        .unwrap()
+});
+
+/// Whether a `#` after `c` belongs to a word like `abc#12` or an HTML entity like `&#39;`.
+///
+/// Only ASCII counts as a word here, as Chinese text runs into citations, e.g. `见#1234567`.
+fn is_glued(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '&'
+}
+
+/// Characters closing a sentence rather than a link.
+const URL_TRAILERS: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"'];
+
+/// What a [`Token`] is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Plain,
+    /// A hole cited as `#1234567`.
+    Citation(HoleID),
+    Url,
+    /// A speaker mentioned as `[Alice]`, only by a pseudonym of the scheme.
+    Mention(Speaker),
+}
+
+/// A span of a text, with its byte offsets in the text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Token<'t> {
+    pub kind: TokenKind,
+    pub text: &'t str,
+    pub span: Range<usize>,
+}
+
+/// Splits `text` into tokens covering it without gaps, merging adjacent plain text.
+///
+/// ```ignore
+/// for token in tokenize(&hole.text) {
+///     if let TokenKind::Citation(id) = token.kind {
+///         dbg!(id, token.span);
+///     }
+/// }
+/// ```
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    // the start of the pending plain text, which also absorbs rejected matches
+    let mut plain = 0;
+    for captures in RICH.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+        let (kind, end) = if captures.name("url").is_some() {
+            (
+                TokenKind::Url,
+                whole.start() + trim_url(whole.as_str()).len(),
+            )
+        } else if let Some(pid) = captures.name("pid") {
+            if text[..whole.start()].ends_with(is_glued) {
+                continue;
+            }
+            match pid.as_str().parse() {
+                Ok(pid) => (TokenKind::Citation(HoleID(pid)), whole.end()),
+                Err(_) => continue,
+            }
+        } else {
+            match Speaker::parse(&captures["name"]) {
+                Speaker::Other(_) => continue,
+                speaker => (TokenKind::Mention(speaker), whole.end()),
+            }
+        };
+        if plain < whole.start() {
+            tokens.push(Token {
+                kind: TokenKind::Plain,
+                text: &text[plain..whole.start()],
+                span: plain..whole.start(),
+            });
+        }
+        tokens.push(Token {
+            kind,
+            text: &text[whole.start()..end],
+            span: whole.start()..end,
+        });
+        plain = end;
+    }
+    if plain < text.len() {
+        tokens.push(Token {
+            kind: TokenKind::Plain,
+            text: &text[plain..],
+            span: plain..text.len(),
+        });
+    }
+    tokens
+}
+
+/// Drops the punctuation ending a sentence after a link, and brackets closing around it.
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let trimmed = url.trim_end_matches(URL_TRAILERS);
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']')) => {
+                let open = if close == ')' { '(' } else { '[' };
+                if trimmed.matches(open).count() < trimmed.matches(close).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed == url {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+/// Holes cited in `text`, in order of first citation.
+pub fn citations(text: &str) -> Vec<HoleID> {
+    let mut cited = Vec::new();
+    for token in tokenize(text) {
+        if let TokenKind::Citation(id) = token.kind {
+            if !cited.contains(&id) {
+                cited.push(id);
+            }
+        }
+    }
+    cited
+}
+
+impl Hole {
+    /// See [`tokenize`].
+    pub fn tokens(&self) -> Vec<Token<'_>> {
+        tokenize(&self.text)
+    }
+
+    /// Holes cited by the hole, see [`citations`].
+    pub fn citations(&self) -> Vec<HoleID> {
+        citations(&self.text)
+    }
+}
+
+impl Reply {
+    /// See [`tokenize`]. The leading addressee marker is already split off into
+    /// [`Reply::reply_to`].
+    pub fn tokens(&self) -> Vec<Token<'_>> {
+        tokenize(&self.text)
+    }
+
+    /// Holes cited by the reply, see [`citations`].
+    pub fn citations(&self) -> Vec<HoleID> {
+        citations(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(text)
+            .into_iter()
+            .map(|token| {
+                assert_eq!(&text[token.span.clone()], token.text);
+                (token.kind, token.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_text() {
+        assert_eq!(
+            kinds("见#1234567和 https://pkuhelper.pku.edu.cn/hole/?q=1。[Alice] 说得对"),
+            [
+                (TokenKind::Plain, "见"),
+                (TokenKind::Citation(HoleID(1234567)), "#1234567"),
+                (TokenKind::Plain, "和 "),
+                (TokenKind::Url, "https://pkuhelper.pku.edu.cn/hole/?q=1"),
+                (TokenKind::Plain, "。"),
+                (TokenKind::Mention(Speaker::Alias(0)), "[Alice]"),
+                (TokenKind::Plain, " 说得对"),
+            ]
+        );
+        assert_eq!(
+            kinds("(see https://en.wikipedia.org/wiki/Rust_(language)). [link] [洞主]"),
+            [
+                (TokenKind::Plain, "(see "),
+                (
+                    TokenKind::Url,
+                    "https://en.wikipedia.org/wiki/Rust_(language)"
+                ),
+                (TokenKind::Plain, "). [link] "),
+                (TokenKind::Mention(Speaker::Dz), "[洞主]"),
+            ]
+        );
+        assert_eq!(
+            kinds("[Naïve Alice] abc#12 &#39; (#34)"),
+            [
+                (TokenKind::Mention(Speaker::Alias(26 * 14)), "[Naïve Alice]"),
+                (TokenKind::Plain, " abc#12 &#39; ("),
+                (TokenKind::Citation(HoleID(34)), "#34"),
+                (TokenKind::Plain, ")"),
+            ]
+        );
+        assert_eq!(kinds(""), []);
+        assert_eq!(kinds("no tokens"), [(TokenKind::Plain, "no tokens")]);
+    }
+
+    #[test]
+    fn collect_citations() {
+        assert_eq!(
+            citations("#2 #1 https://example.com/#3 #2 #99999999999999999999999"),
+            [HoleID(2), HoleID(1)]
+        );
+    }
+}
@@ -22,7 +22,7 @@ pub mod raw_timestamp {
     }
 
     fn from_timestamp(timestamp: i64) -> DateTime<Utc> {
-        DEFAULT_TIMEZONE_OFFSET.timestamp(timestamp, 0).with_timezone(&Utc)
+        DEFAULT_TIMEZONE_OFFSET.timestamp_opt(timestamp, 0).unwrap().with_timezone(&Utc)
     }
 
     /* pub fn deserialize<'de, D>(d: D) -> Result<DateTime<Utc>, D::Error>
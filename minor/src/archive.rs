@@ -0,0 +1,385 @@
+use std::{collections::BTreeSet, path::Path};
+
+use chrono::{DateTime, TimeZone, Utc};
+use major::hole::{
+    reply::{Reply, ReplyEntry, ReplyID},
+    Hole, HoleEntry, HoleID, HoleKind,
+};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use thiserror::Error;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS holes (
+    id INTEGER PRIMARY KEY,
+    text TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    url TEXT,
+    timestamp INTEGER NOT NULL,
+    reply INTEGER NOT NULL,
+    likenum INTEGER NOT NULL,
+    tag TEXT,
+    snapshot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS holes_timestamp ON holes (timestamp);
+CREATE INDEX IF NOT EXISTS holes_tag ON holes (tag);
+
+CREATE TABLE IF NOT EXISTS counters (
+    hole INTEGER NOT NULL,
+    snapshot INTEGER NOT NULL,
+    reply INTEGER NOT NULL,
+    likenum INTEGER NOT NULL,
+    PRIMARY KEY (hole, snapshot)
+);
+
+CREATE TABLE IF NOT EXISTS replies (
+    id INTEGER PRIMARY KEY,
+    hole INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    text TEXT NOT NULL,
+    dz INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    tag TEXT,
+    snapshot INTEGER NOT NULL,
+    reply_to TEXT
+);
+CREATE INDEX IF NOT EXISTS replies_hole ON replies (hole);
+";
+
+const HOLE_COLUMNS: &str = "id, text, kind, url, timestamp, reply, likenum, tag, snapshot";
+const REPLY_COLUMNS: &str = "id, hole, name, text, dz, timestamp, tag, snapshot, reply_to";
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("SQLite fails.")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// A stored row cannot be turned back into an entry.
+    #[error("Malformed {table} row {id}: {reason}.")]
+    Malformed {
+        table: &'static str,
+        id: i64,
+        reason: String,
+    },
+}
+
+/// The counters of a hole at a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterSnapshot {
+    pub snapshot: DateTime<Utc>,
+    pub reply: usize,
+    pub likenum: usize,
+}
+
+/// A persistent archive of holes and replies in an SQLite database.
+///
+/// Every entry keeps its newest snapshot. Older snapshots never overwrite newer ones,
+/// so entries can be archived in any order. The `reply` and `likenum` counters of
+/// every archived hole snapshot are kept, see [`Archive::counter_history`].
+///
+/// ```ignore
+/// let mut archive = Archive::open("holes.sqlite")?;
+/// archive.insert_holes(&holes)?;
+/// let tagged = archive.holes_tagged("性相关")?;
+/// ```
+pub struct Archive {
+    conn: Connection,
+}
+
+impl Archive {
+    /// Opens or creates the archive at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Opens an archive living in memory, mostly for tests.
+    pub fn open_in_memory() -> Result<Self, ArchiveError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, ArchiveError> {
+        conn.execute_batch(SCHEMA)?;
+        // archives created before `reply_to` lack the column
+        let has_reply_to: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('replies') WHERE name = 'reply_to'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_reply_to {
+            conn.execute_batch("ALTER TABLE replies ADD COLUMN reply_to TEXT;")?;
+        }
+        Ok(Archive { conn })
+    }
+
+    /// Archives `holes`, returning the number of holes whose newest snapshot changes.
+    pub fn insert_holes<'h>(
+        &mut self,
+        holes: impl IntoIterator<Item = &'h HoleEntry>,
+    ) -> Result<usize, ArchiveError> {
+        let tx = self.conn.transaction()?;
+        let mut changed = 0;
+        {
+            let mut upsert = tx.prepare(&format!(
+                "INSERT INTO holes ({HOLE_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT (id) DO UPDATE SET
+                    text = excluded.text, kind = excluded.kind, url = excluded.url,
+                    timestamp = excluded.timestamp, reply = excluded.reply,
+                    likenum = excluded.likenum, tag = excluded.tag, snapshot = excluded.snapshot
+                WHERE excluded.snapshot >= holes.snapshot"
+            ))?;
+            let mut record = tx.prepare(
+                "INSERT OR REPLACE INTO counters (hole, snapshot, reply, likenum)
+                VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for HoleEntry { entry, snapshot } in holes {
+                let (kind, url) = match &entry.kind {
+                    HoleKind::Text => ("text", None),
+                    HoleKind::Image { url } => ("image", Some(url)),
+                    HoleKind::Audio { url } => ("audio", Some(url)),
+                };
+                changed += upsert.execute(params![
+                    entry.id.0 as i64,
+                    entry.text,
+                    kind,
+                    url,
+                    entry.timestamp.timestamp(),
+                    entry.reply as i64,
+                    entry.likenum as i64,
+                    entry.tag,
+                    snapshot.timestamp(),
+                ])?;
+                record.execute(params![
+                    entry.id.0 as i64,
+                    snapshot.timestamp(),
+                    entry.reply as i64,
+                    entry.likenum as i64,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// Archives `replies`, returning the number of replies whose newest snapshot changes.
+    pub fn insert_replies<'r>(
+        &mut self,
+        replies: impl IntoIterator<Item = &'r ReplyEntry>,
+    ) -> Result<usize, ArchiveError> {
+        let tx = self.conn.transaction()?;
+        let mut changed = 0;
+        {
+            let mut upsert = tx.prepare(&format!(
+                "INSERT INTO replies ({REPLY_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT (id) DO UPDATE SET
+                    hole = excluded.hole, name = excluded.name, text = excluded.text,
+                    dz = excluded.dz, timestamp = excluded.timestamp, tag = excluded.tag,
+                    snapshot = excluded.snapshot, reply_to = excluded.reply_to
+                WHERE excluded.snapshot >= replies.snapshot"
+            ))?;
+            for ReplyEntry { entry, snapshot } in replies {
+                changed += upsert.execute(params![
+                    entry.id.0 as i64,
+                    entry.hole.0 as i64,
+                    entry.name,
+                    entry.text,
+                    entry.dz,
+                    entry.timestamp.timestamp(),
+                    entry.tag,
+                    snapshot.timestamp(),
+                    entry.reply_to,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// Returns the newest snapshot of a hole.
+    pub fn hole(&self, id: HoleID) -> Result<Option<HoleEntry>, ArchiveError> {
+        self.conn
+            .query_row(
+                &format!("SELECT {HOLE_COLUMNS} FROM holes WHERE id = ?1"),
+                [id.0 as i64],
+                RawHoleRow::from_row,
+            )
+            .optional()?
+            .map(HoleEntry::try_from)
+            .transpose()
+    }
+
+    /// Returns holes posted in `[from, to)`.
+    pub fn holes_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<BTreeSet<HoleEntry>, ArchiveError> {
+        self.query_holes(
+            "timestamp >= ?1 AND timestamp < ?2",
+            params![from.timestamp(), to.timestamp()],
+        )
+    }
+
+    /// Returns holes tagged `tag`.
+    pub fn holes_tagged(&self, tag: &str) -> Result<BTreeSet<HoleEntry>, ArchiveError> {
+        self.query_holes("tag = ?1", params![tag])
+    }
+
+    /// Returns the replies of a hole.
+    pub fn replies(&self, hole: HoleID) -> Result<BTreeSet<ReplyEntry>, ArchiveError> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {REPLY_COLUMNS} FROM replies WHERE hole = ?1"
+        ))?;
+        let rows = statement.query_map([hole.0 as i64], RawReplyRow::from_row)?;
+        rows.map(|row| ReplyEntry::try_from(row?)).collect()
+    }
+
+    /// Returns the counters of a hole at every archived snapshot, oldest first.
+    pub fn counter_history(&self, hole: HoleID) -> Result<Vec<CounterSnapshot>, ArchiveError> {
+        let mut statement = self.conn.prepare(
+            "SELECT snapshot, reply, likenum FROM counters WHERE hole = ?1 ORDER BY snapshot",
+        )?;
+        let rows = statement.query_map([hole.0 as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (snapshot, reply, likenum) = row?;
+            Ok(CounterSnapshot {
+                snapshot: timestamp("counters", hole.0 as i64, snapshot)?,
+                reply: reply as usize,
+                likenum: likenum as usize,
+            })
+        })
+        .collect()
+    }
+
+    fn query_holes(
+        &self,
+        condition: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<BTreeSet<HoleEntry>, ArchiveError> {
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {HOLE_COLUMNS} FROM holes WHERE {condition}"
+        ))?;
+        let rows = statement.query_map(params, RawHoleRow::from_row)?;
+        rows.map(|row| HoleEntry::try_from(row?)).collect()
+    }
+}
+
+fn timestamp(table: &'static str, id: i64, secs: i64) -> Result<DateTime<Utc>, ArchiveError> {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| ArchiveError::Malformed {
+            table,
+            id,
+            reason: format!("invalid timestamp {}", secs),
+        })
+}
+
+struct RawHoleRow {
+    id: i64,
+    text: String,
+    kind: String,
+    url: Option<String>,
+    timestamp: i64,
+    reply: i64,
+    likenum: i64,
+    tag: Option<String>,
+    snapshot: i64,
+}
+
+impl RawHoleRow {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(RawHoleRow {
+            id: row.get(0)?,
+            text: row.get(1)?,
+            kind: row.get(2)?,
+            url: row.get(3)?,
+            timestamp: row.get(4)?,
+            reply: row.get(5)?,
+            likenum: row.get(6)?,
+            tag: row.get(7)?,
+            snapshot: row.get(8)?,
+        })
+    }
+}
+
+impl TryFrom<RawHoleRow> for HoleEntry {
+    type Error = ArchiveError;
+
+    fn try_from(row: RawHoleRow) -> Result<Self, Self::Error> {
+        let malformed = |reason: &str| ArchiveError::Malformed {
+            table: "holes",
+            id: row.id,
+            reason: String::from(reason),
+        };
+        let kind = match (row.kind.as_str(), row.url) {
+            ("text", _) => HoleKind::Text,
+            ("image", Some(url)) => HoleKind::Image { url },
+            ("audio", Some(url)) => HoleKind::Audio { url },
+            _ => return Err(malformed("unknown kind or missing url")),
+        };
+        Ok(HoleEntry {
+            entry: Hole {
+                id: HoleID(row.id as usize),
+                text: row.text,
+                kind,
+                timestamp: timestamp("holes", row.id, row.timestamp)?,
+                reply: row.reply as usize,
+                likenum: row.likenum as usize,
+                tag: row.tag,
+            },
+            snapshot: timestamp("holes", row.id, row.snapshot)?,
+        })
+    }
+}
+
+struct RawReplyRow {
+    id: i64,
+    hole: i64,
+    name: String,
+    text: String,
+    dz: bool,
+    timestamp: i64,
+    tag: Option<String>,
+    snapshot: i64,
+    reply_to: Option<String>,
+}
+
+impl RawReplyRow {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(RawReplyRow {
+            id: row.get(0)?,
+            hole: row.get(1)?,
+            name: row.get(2)?,
+            text: row.get(3)?,
+            dz: row.get(4)?,
+            timestamp: row.get(5)?,
+            tag: row.get(6)?,
+            snapshot: row.get(7)?,
+            reply_to: row.get(8)?,
+        })
+    }
+}
+
+impl TryFrom<RawReplyRow> for ReplyEntry {
+    type Error = ArchiveError;
+
+    fn try_from(row: RawReplyRow) -> Result<Self, Self::Error> {
+        Ok(ReplyEntry {
+            entry: Reply {
+                id: ReplyID(row.id as usize),
+                hole: HoleID(row.hole as usize),
+                name: row.name,
+                reply_to: row.reply_to,
+                text: row.text,
+                dz: row.dz,
+                timestamp: timestamp("replies", row.id, row.timestamp)?,
+                tag: row.tag,
+            },
+            snapshot: timestamp("replies", row.id, row.snapshot)?,
+        })
+    }
+}
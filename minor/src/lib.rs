@@ -0,0 +1,121 @@
+//! A persistent local archive of holes and replies.
+
+pub mod archive;
+
+pub use archive::{Archive, ArchiveError, CounterSnapshot};
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use major::hole::{
+        reply::{Reply, ReplyEntry, ReplyID},
+        Hole, HoleEntry, HoleID, HoleKind,
+    };
+
+    use crate::{Archive, CounterSnapshot};
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(secs, 0).unwrap()
+    }
+
+    fn hole(id: usize, likenum: usize, tag: Option<&str>, snapshot: i64) -> HoleEntry {
+        HoleEntry {
+            entry: Hole {
+                id: HoleID(id),
+                text: format!("Hole {}.", id),
+                kind: if id % 2 == 0 {
+                    HoleKind::Image {
+                        url: format!("{}.jpeg", id),
+                    }
+                } else {
+                    HoleKind::Text
+                },
+                timestamp: at(1_600_000_000 + id as i64 * 60),
+                reply: likenum / 2,
+                likenum,
+                tag: tag.map(String::from),
+            },
+            snapshot: at(snapshot),
+        }
+    }
+
+    #[test]
+    fn archive_holes() {
+        let mut archive = Archive::open_in_memory().unwrap();
+        let newer = [hole(1, 5, Some("tag"), 2000), hole(2, 0, None, 2000)];
+        let older = [hole(1, 1, None, 1000), hole(3, 0, Some("tag"), 1000)];
+        assert_eq!(archive.insert_holes(&newer).unwrap(), 2);
+        assert_eq!(archive.insert_holes(&older).unwrap(), 1);
+
+        let first = archive.hole(HoleID(1)).unwrap().unwrap();
+        assert_eq!(first, newer[0]);
+        assert_eq!(first.entry.likenum, 5);
+        assert_eq!(first.entry.tag.as_deref(), Some("tag"));
+        assert_eq!(
+            archive.hole(HoleID(2)).unwrap().unwrap().entry.kind,
+            HoleKind::Image {
+                url: String::from("2.jpeg")
+            }
+        );
+        assert!(archive.hole(HoleID(4)).unwrap().is_none());
+
+        let ids = |holes: std::collections::BTreeSet<HoleEntry>| {
+            holes.into_iter().map(|h| h.entry.id.0).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(archive.holes_tagged("tag").unwrap()), [1, 3]);
+        assert_eq!(
+            ids(archive
+                .holes_between(at(1_600_000_060), at(1_600_000_180))
+                .unwrap()),
+            [1, 2]
+        );
+
+        assert_eq!(
+            archive.counter_history(HoleID(1)).unwrap(),
+            [
+                CounterSnapshot {
+                    snapshot: at(1000),
+                    reply: 0,
+                    likenum: 1
+                },
+                CounterSnapshot {
+                    snapshot: at(2000),
+                    reply: 2,
+                    likenum: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn archive_replies() {
+        let mut archive = Archive::open_in_memory().unwrap();
+        let replies = (0..3)
+            .map(|i| ReplyEntry {
+                entry: Reply {
+                    id: ReplyID(100 + i),
+                    hole: HoleID(1 + i / 2),
+                    name: String::from(if i == 0 { "洞主" } else { "Alice" }),
+                    reply_to: (i > 0).then(|| String::from("洞主")),
+                    text: format!("Reply {}.", i),
+                    dz: i == 0,
+                    timestamp: at(1_600_000_000 + i as i64),
+                    tag: None,
+                },
+                snapshot: at(1000),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(archive.insert_replies(&replies).unwrap(), 3);
+
+        let stored = archive.replies(HoleID(1)).unwrap();
+        assert_eq!(stored.len(), 2);
+        let first = stored.iter().next().unwrap();
+        assert_eq!(first.entry.name, "洞主");
+        assert!(first.entry.dz);
+        assert_eq!(first.entry.timestamp, at(1_600_000_000));
+        assert_eq!(first.entry.reply_to, None);
+        let second = stored.iter().nth(1).unwrap();
+        assert_eq!(second.entry.reply_to.as_deref(), Some("洞主"));
+        assert_eq!(archive.replies(HoleID(2)).unwrap().len(), 1);
+    }
+}